@@ -0,0 +1,810 @@
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, ValueEnum};
+use clap_complete::Shell;
+use clean_cargo_projects::{CleanProfile, ProjectKind, SizeUnits};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 自动扫描并清理 Cargo 项目的 target 目录
+#[derive(Parser, Debug)]
+#[command(version, about, args_override_self = true)]
+pub struct Args {
+    /// 扫描起点目录（可重复），默认为当前工作目录
+    ///
+    /// `-` 表示从标准输入逐行读取扫描起点（忽略空行）；多个起点重叠时同一项目只处理一次
+    #[arg(long)]
+    pub path: Vec<PathBuf>,
+
+    /// 未指定 `--path` 时从程序所在目录开始扫描（旧版默认行为），无法确定时回退到当前工作目录
+    #[arg(long, conflicts_with = "path")]
+    pub use_exe_dir: bool,
+
+    /// 配置文件路径，指定后不再读取默认位置的配置文件
+    ///
+    /// 默认依次读取 `~/.config/clean_cargo_projects.toml` 与扫描起点下的同名文件，
+    /// 配置项名即长参数名（如 `ask-mode = "menu"`），命令行参数优先，与之冲突的配置项被忽略；
+    /// 配置文件中开启的开关可用 `--no-<开关>` 关闭（如 `--no-scan-hidden`）
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// 询问方式：real-time 遍历时逐个询问；menu 遍历结束后在可模糊过滤的菜单中勾选；
+    /// tui 遍历结束后在复选框列表中勾选；auto 遍历结束后按阈值自动选择；
+    /// glob 遍历结束后选中路径匹配 --select 的项目
+    #[arg(long, value_enum, default_value_t = AskMode::RealTime)]
+    pub ask_mode: AskMode,
+
+    /// glob 模式下选中路径匹配该规则的项目（可重复，匹配任一条即选中）
+    ///
+    /// `*` 不跨越 `/`，`**` 匹配任意层目录；开头的 `~/` 展开为家目录
+    #[arg(long, value_name = "GLOB")]
+    pub select: Vec<String>,
+
+    /// 遍历结束后项目列表的排序方式（作用于 menu、auto 等先收集再处理的模式）
+    #[arg(long, value_enum, default_value_t = SortKey::Depth)]
+    pub sort: SortKey,
+
+    /// --sort 相同的项目之间的排序方式，如 --sort depth 时同一深度内按大小排列；默认保持遍历顺序
+    #[arg(long, value_enum, value_name = "KEY")]
+    pub sort_secondary: Option<SecondarySortKey>,
+
+    /// 遍历结束后只保留排序后的前 N 个项目，其余不显示也不清理（配合 --sort size 保留最大的）
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_results: Option<u64>,
+
+    /// 反转遍历结束后的项目列表顺序（作用于 menu、auto 等先收集再处理的模式）
+    #[arg(long)]
+    pub reverse: bool,
+
+    /// auto 模式下选中 target 大小不低于该百分位的项目（如 80 表示最大的约 20%）
+    #[arg(long, value_name = "P", value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub threshold_percentile: Option<u8>,
+
+    /// auto 模式下保留 target 最大的 N 个项目，清理其余全部项目
+    #[arg(long, value_name = "N", conflicts_with = "threshold_percentile")]
+    pub keep_largest: Option<usize>,
+
+    /// 已默认启用，保留以兼容旧的命令行与配置文件：
+    /// auto 模式未指定选择规则（--threshold-percentile 或 --keep-largest）时总是报错退出
+    #[arg(long)]
+    pub abort_on_no_threshold: bool,
+
+    /// auto 模式清理前不再确认（也可设置环境变量 CLEAN_CARGO_ASSUME_YES=1）
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// 按 auto 模式的选择规则列出将被清理的项目后退出，不执行清理
+    #[arg(long)]
+    pub list_only_clean_candidates: bool,
+
+    /// 只统计找到的项目，按大小从大到小列出并给出总大小后退出，不询问也不清理
+    ///
+    /// 仍遵循 --min-size、--exclude 等过滤条件，可配合 --csv 导出
+    #[arg(long, conflicts_with_all = ["list_only_clean_candidates", "compare_threshold"])]
+    pub stats_only: bool,
+
+    /// 报告格式：human 为面向终端的文字输出（默认）；json、json-lines、csv、markdown 为机器可读的报告
+    ///
+    /// 未指定 --output 时报告写到标准输出：只扫描，不询问也不清理，不输出遍历进度，警告写到标准错误；
+    /// 指定 --output 时照常运行，结束时将每个项目的处理结果写入该文件
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = OutputFormat::Human)]
+    pub output_format: OutputFormat,
+
+    /// 将 --output-format 指定的报告写入该文件，而非标准输出
+    #[arg(long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// 已弃用，等同于 --output-format json
+    #[arg(long, hide = true, conflicts_with = "output_format")]
+    pub json: bool,
+
+    /// 等同于 --output-format json-lines：每找到一个通过过滤条件的项目立即输出一行
+    /// `{"event":"found",...}`，结束时输出一行 `{"event":"summary",...}`，便于用 jq 等工具处理
+    #[arg(long, conflicts_with_all = ["output_format", "json"])]
+    pub json_lines: bool,
+
+    /// 列出 target 大小接近该阈值的项目（如 500MB），用于调整阈值，不执行清理
+    #[arg(long, value_name = "SIZE", value_parser = parse_size_arg)]
+    pub compare_threshold: Option<String>,
+
+    /// 阈值对比的范围（百分比）
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = 20.0,
+        requires = "compare_threshold"
+    )]
+    pub compare_margin: f64,
+
+    /// 安静模式：不打印遍历过程与逐个项目的发现、跳过信息，只输出询问、清理结果与最终统计
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// 详细输出（可叠加）：-v 显示完整路径、各阶段耗时与每个项目统计大小的耗时；
+    /// -vv 额外显示每个 target 中的文件数
+    #[arg(short, long, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// 结束时按扫描起点下的一级目录汇总释放的空间和项目数
+    #[arg(long)]
+    pub summary_table: bool,
+
+    /// 结束时将每个项目及其处理结果导出为 CSV 文件（同 `--output-format csv --output FILE`，可与其他报告同时使用）
+    ///
+    /// 列为 path,target_size,target_bytes,selected,cleaned,error,attempts,remaining_bytes,trashed,
+    /// measure_ms,clean_ms；后两列为统计 target 大小与清理（含重试）的耗时（毫秒）
+    #[arg(long, value_name = "FILE")]
+    pub csv: Option<PathBuf>,
+
+    /// 结束时将项目导出为 Markdown 表格（列为序号、路径、大小、深度，附合计与生成时间），便于分享扫描结果
+    ///
+    /// 同 `--output-format markdown --output FILE`，可与其他报告同时使用
+    #[arg(long, value_name = "FILE")]
+    pub markdown: Option<PathBuf>,
+
+    /// 不遍历目录，改为处理此前导出的扫描结果中的项目（重新统计大小，跳过已不存在的项目）
+    ///
+    /// 读取 json、json-lines 或 CSV 格式的报告（--output-format、--json-lines、--csv 的输出），只使用其中的路径
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["path", "use_exe_dir"])]
+    pub from_scan: Option<PathBuf>,
+
+    /// 输出大小时使用的单位制：binary 按 1024 进制并标为 KB/MB/GB（默认，与旧版一致）；
+    /// iec 按 1024 进制并标为 KiB/MiB/GiB；si 按 1000 进制并标为 KB/MB/GB
+    ///
+    /// 大小参数（如 `--min-size`）中的 KB、MB 等与输出一致，si 时按 1000 进制；KiB、MiB 等总是按 1024 进制
+    #[arg(long, value_enum, default_value_t = SizeUnits::Binary)]
+    pub units: SizeUnits,
+
+    /// 何时使用彩色输出（auto 时输出到终端且未设置 NO_COLOR 才着色）
+    #[arg(long, value_enum, default_value_t = ColorMode::Auto)]
+    pub color: ColorMode,
+
+    /// 关闭彩色输出，等同于 `--color never`（保留以兼容旧的命令行与配置文件）
+    #[arg(long, conflicts_with = "color")]
+    pub no_color: bool,
+
+    /// 进度与汇总中的装饰符号只使用 ASCII 字符（关闭彩色输出时自动启用）
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// 不读写目录大小缓存，每次重新统计所有 target 的大小
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// 清除目录大小缓存后再扫描
+    #[arg(long)]
+    pub clear_cache: bool,
+
+    /// 已有其他实例运行（持有锁）时的处理方式
+    #[arg(long, value_enum, default_value_t = OnLock::Abort)]
+    pub on_lock: OnLock,
+
+    /// 不加实例锁，适用于每次运行环境相互隔离的 CI；与其他实例同时清理同一批项目时可能相互干扰
+    #[arg(long, conflicts_with = "on_lock")]
+    pub no_lock: bool,
+
+    /// 要识别的项目类型（可重复），默认只识别 Cargo 项目
+    ///
+    /// node 识别含 package.json 的 node_modules/，python 识别含 pyproject.toml 的 __pycache__/；
+    /// 非 Rust 项目直接删除其构建目录（除非给出 `--clean-command`）
+    #[arg(long, value_enum, value_name = "KIND", default_values_t = [ProjectKind::Rust])]
+    pub detect: Vec<ProjectKind>,
+
+    /// 遍历时也进入隐藏目录（名称以 `.` 开头，如 .git、.cache）
+    ///
+    /// 默认跳过隐藏目录；扫描起点本身即使是隐藏目录也会照常遍历
+    #[arg(long)]
+    pub scan_hidden: bool,
+
+    /// 只处理深度不小于该值的项目（扫描起点为 0，其直接子目录为 1）
+    ///
+    /// 更浅的目录仍会被遍历，只是其中的项目不会被列出或清理
+    #[arg(long, value_name = "N")]
+    pub min_depth: Option<u32>,
+
+    /// 不进入匹配该规则的目录（可重复），语法与 `.gitignore` 相同，相对于扫描起点
+    ///
+    /// 如 `node_modules` 匹配任意位置的同名目录，`/experiments` 与 `*/vendor` 从扫描起点开始匹配；
+    /// 位于扫描起点内的绝对路径按其相对位置匹配
+    #[arg(long, value_name = "PATTERN")]
+    pub exclude: Vec<String>,
+
+    /// 只处理位于匹配该规则的目录中的项目（可重复），语法与 --exclude 相同；
+    /// 给出后项目须至少匹配一条包含规则且不匹配任何排除规则，遍历本身不受影响
+    #[arg(long, value_name = "PATTERN")]
+    pub include: Vec<String>,
+
+    /// 从文件读取排除规则（可重复）：每行一条，忽略空行与 `#` 注释
+    #[arg(long, value_name = "FILE")]
+    pub exclude_from: Vec<PathBuf>,
+
+    /// 遍历时读取每层目录的 `.gitignore` 与 `.ignore`，跳过其中忽略的子目录
+    ///
+    /// 内层规则叠加在外层之上；项目自身被忽略的 target 目录不影响该项目的识别
+    #[arg(long)]
+    pub respect_gitignore: bool,
+
+    /// 跟随指向目录的符号链接继续遍历（会记录已访问目录，避免链接成环）
+    ///
+    /// 默认不进入符号链接指向的目录
+    #[arg(long)]
+    pub follow_symlinks: bool,
+
+    /// 也进入已找到项目的 target 目录，发现其中嵌套的项目（如会构建的测试夹具）
+    ///
+    /// 默认不进入：嵌套项目的 target 已计入外层项目的 target 大小，随外层项目一并清理
+    #[arg(long)]
+    pub scan_inside_target: bool,
+
+    /// 仅处理 target 与源码大小之比不低于该值的项目（如 10 表示 target 至少为源码的 10 倍）
+    ///
+    /// 指定后会额外计算每个项目的源码大小，并在列表中显示比例
+    #[arg(long, value_name = "RATIO")]
+    pub min_target_ratio: Option<f64>,
+
+    /// 忽略 target 小于该大小的项目（如 50MB），它们不会被列出，也不会在任何询问方式中出现
+    #[arg(long, value_name = "SIZE", value_parser = parse_size_arg)]
+    pub min_size: Option<String>,
+
+    /// 同时列出 target 为空（0 字节）的项目，默认忽略；仍受 --min-size 限制
+    #[arg(long)]
+    pub include_empty: bool,
+
+    /// 只处理 target 中最新文件的修改时间早于该时长之前的项目（如 30d，支持 h、d、w）
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_arg)]
+    pub older_than: Option<Duration>,
+
+    /// 受保护项目列表文件：每行一个项目路径（忽略空行与 `#` 注释），其中的项目永远不会被清理
+    #[arg(long, value_name = "FILE")]
+    pub protect_file: Option<PathBuf>,
+
+    /// 校验 target 目录确实由同目录的 Cargo.toml 构建产生
+    ///
+    /// 在 target/debug、target/release 中查找与包名对应的构建产物，不匹配的项目会给出警告并跳过
+    #[arg(long)]
+    pub verify_manifest: bool,
+
+    /// 只清理没有未提交修改的项目：在项目目录执行 git status --porcelain（不含构建目录），
+    /// 有修改或未跟踪的文件时跳过
+    #[arg(long)]
+    pub only_clean_git_clean: bool,
+
+    /// 配合 --only-clean-git-clean，不在 git 仓库中的项目如何处理
+    #[arg(long, value_enum, default_value_t = NonGit::Include, requires = "only_clean_git_clean")]
+    pub non_git: NonGit,
+
+    /// 单次 cargo clean 的超时时间（秒），超时后终止该进程并记为失败
+    #[arg(long, value_name = "SECS", value_parser = parse_secs)]
+    pub clean_timeout: Option<Duration>,
+
+    /// cargo clean 失败后的重试次数，每次重试前的等待时间从 0.5 秒起成倍增加
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub retries: u32,
+
+    /// 清理成功后重新统计 target 大小，残留超过 1MB 时给出警告（如 cargo clean 未删除的只读文件）
+    #[arg(long)]
+    pub verify: bool,
+
+    /// 任一项目清理失败时停止清理后续项目（默认继续，结束时以退出码 4 报告失败）
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// 遍历结束后统一清理选中项目时的顺序：serial 逐个清理；parallel 同时清理多个项目；
+    /// per-disk 同一设备上的项目逐个清理，不同设备之间并行
+    ///
+    /// 不影响 real-time 模式（遍历时逐个询问并清理）；parallel 与 per-disk 不能与 --limit-rate 一起使用
+    #[arg(long, value_enum, default_value_t = CleanOrder::Serial)]
+    pub clean_order: CleanOrder,
+
+    /// 预览模式：照常询问与选择，但不执行清理，只报告每个项目及合计将释放的空间
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// 限制删除速率（如 50MB/s），按累计删除量控制 cargo clean 的派发节奏
+    #[arg(long, value_name = "SIZE/s", value_parser = parse_rate)]
+    pub limit_rate: Option<String>,
+
+    /// 只删除 target 下的 incremental 编译缓存，而不是执行 cargo clean
+    ///
+    /// incremental 缓存只用于加速重新编译，删除后不影响已构建的产物
+    #[arg(long)]
+    pub incremental_only: bool,
+
+    /// 清理方式：cargo 在项目中执行 `cargo clean`；rmdir 直接删除 target 目录，不需要 cargo
+    ///
+    /// target 为符号链接时 rmdir 保留链接本身，只清空其指向的目录
+    #[arg(long, value_enum, default_value_t = CleanMethod::Cargo)]
+    pub method: CleanMethod,
+
+    /// 直接删除构建目录时（`--method rmdir` 或非 Rust 项目）移入系统回收站，而非永久删除
+    ///
+    /// 平台不支持回收站或移动失败时给出警告后永久删除
+    #[arg(long, conflicts_with = "clean_command")]
+    pub trash: bool,
+
+    /// 用自定义命令代替 `cargo clean`，在每个项目目录中执行，参数中的 `{dir}` 替换为项目路径
+    ///
+    /// 按空白拆分参数，引号内的空白不拆分，如 `--clean-command "cargo clean -p mycrate"`
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = parse_command,
+        conflicts_with_all = ["method", "clean_profile", "cargo_args", "incremental_only"]
+    )]
+    pub clean_command: Option<String>,
+
+    /// 只清理指定 profile 的产物：debug 执行 `cargo clean --profile dev`，
+    /// release 执行 `cargo clean --release`；列表中的大小也只计对应的 target 子目录
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = CleanProfile::All,
+        conflicts_with = "incremental_only"
+    )]
+    pub clean_profile: CleanProfile,
+
+    /// 追加到 `cargo clean` 命令的额外参数（可重复，如 `--cargo-arg=--release`）
+    ///
+    /// 只允许不改变清理位置的参数：`--release`、`--profile`、`--target`、`-p`、`-v`、`--frozen`、
+    /// `--offline` 等；带值参数的值写在 `=` 后或作为下一个 `--cargo-arg`
+    #[arg(long = "cargo-arg", value_name = "FLAG", allow_hyphen_values = true, value_parser = parse_cargo_arg)]
+    pub cargo_args: Vec<String>,
+
+    /// 输出常用参数组合的示例后退出，不进行扫描
+    #[arg(long)]
+    pub help_examples: bool,
+
+    /// 输出指定 shell 的补全脚本后退出，不进行扫描
+    #[arg(long, value_name = "SHELL", value_enum, hide = true)]
+    pub generate_completions: Option<Shell>,
+}
+
+/// 允许透传给 cargo clean 的开关（不带值）
+///
+/// 只允许不会改变清理位置的参数；`--config`、`-Z`、`-C`、`--manifest-path`、`--target-dir`
+/// 等都可能让 cargo clean 删除项目以外的目录
+const ALLOWED_CARGO_SWITCHES: &[&str] = &[
+    "--release",
+    "-r",
+    "--doc",
+    "--dry-run",
+    "--frozen",
+    "--locked",
+    "--offline",
+    "-v",
+    "-vv",
+    "-vvv",
+    "--verbose",
+    "-q",
+    "--quiet",
+];
+
+/// 允许透传给 cargo clean 的带值参数，值可写作 `--profile=dev` 或紧随其后的下一个 `--cargo-arg`
+const ALLOWED_CARGO_OPTIONS: &[&str] = &["--profile", "--target", "-p", "--package", "--color"];
+
+/// 校验单个透传给 cargo clean 的参数：以 `-` 开头时必须是允许的参数，其余视为前一个参数的值
+fn parse_cargo_arg(s: &str) -> Result<String, String> {
+    if !s.starts_with('-') {
+        return Ok(s.to_string());
+    }
+    let allowed = match s.split_once('=') {
+        Some((flag, _)) => ALLOWED_CARGO_OPTIONS.contains(&flag),
+        None => ALLOWED_CARGO_SWITCHES.contains(&s) || ALLOWED_CARGO_OPTIONS.contains(&s),
+    };
+    if !allowed {
+        return Err(format!(
+            "不允许透传该参数: {}（可用: {}、{}）",
+            s,
+            ALLOWED_CARGO_SWITCHES.join("、"),
+            ALLOWED_CARGO_OPTIONS.join("、")
+        ));
+    }
+    Ok(s.to_string())
+}
+
+/// 校验透传给 cargo clean 的参数序列：带值参数后必须紧跟其值，值不能单独出现
+fn validate_cargo_args(cargo_args: &[String]) -> Result<(), String> {
+    let mut iter = cargo_args.iter();
+    while let Some(arg) = iter.next() {
+        if ALLOWED_CARGO_OPTIONS.contains(&arg.as_str()) {
+            match iter.next() {
+                Some(value) if !value.starts_with('-') => {}
+                _ => return Err(format!("--cargo-arg={} 后缺少参数值", arg)),
+            }
+        } else if !arg.starts_with('-') {
+            return Err(format!("--cargo-arg={} 不是允许的参数的值", arg));
+        }
+    }
+    Ok(())
+}
+
+impl Args {
+    /// 实际使用的报告格式：合并 `--json`、`--json-lines` 与 `--output-format`
+    pub fn output_format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else if self.json_lines {
+            OutputFormat::JsonLines
+        } else {
+            self.output_format
+        }
+    }
+
+    /// 写到标准输出的报告格式；为 Some 时只扫描并输出报告，不输出面向终端的文字
+    pub fn stdout_report(&self) -> Option<OutputFormat> {
+        let format = self.output_format();
+        (format != OutputFormat::Human && self.output.is_none()).then_some(format)
+    }
+
+    /// 要生成的全部报告及其写入的文件（None 为标准输出），包括 --csv 与 --markdown
+    pub fn reports(&self) -> Vec<(OutputFormat, Option<PathBuf>)> {
+        let mut reports = Vec::new();
+        if self.output_format() != OutputFormat::Human {
+            reports.push((self.output_format(), self.output.clone()));
+        }
+        if let Some(file) = &self.csv {
+            reports.push((OutputFormat::Csv, Some(file.clone())));
+        }
+        if let Some(file) = &self.markdown {
+            reports.push((OutputFormat::Markdown, Some(file.clone())));
+        }
+        reports
+    }
+
+    /// 实际使用的彩色输出方式：给出 `--no-color` 时为 never
+    pub fn color_mode(&self) -> ColorMode {
+        if self.no_color {
+            ColorMode::Never
+        } else {
+            self.color
+        }
+    }
+
+    /// `--min-size` 按 `--units` 换算后的字节数
+    pub fn min_size_bytes(&self) -> Option<u64> {
+        let size = self.min_size.as_deref()?;
+        crate::parse_size(size, self.units)
+    }
+
+    /// `--compare-threshold` 按 `--units` 换算后的字节数
+    pub fn compare_threshold_bytes(&self) -> Option<u64> {
+        let size = self.compare_threshold.as_deref()?;
+        crate::parse_size(size, self.units)
+    }
+
+    /// `--limit-rate` 按 `--units` 换算后的字节/秒
+    pub fn limit_rate_bytes(&self) -> Option<u64> {
+        rate_size(self.limit_rate.as_deref()?, self.units)
+    }
+}
+
+/// 校验 clap 无法表达的参数组合，在扫描前调用，避免扫描结束后才发现无法按要求清理
+pub fn validate_args(args: &Args) -> Result<()> {
+    // auto 模式缺少选择规则时不会清理任何项目，直接报错，避免误以为没有可清理的项目
+    let uses_auto = args.ask_mode == AskMode::Auto || args.list_only_clean_candidates;
+    let has_policy = args.threshold_percentile.is_some() || args.keep_largest.is_some();
+    if uses_auto && !has_policy && !args.stats_only {
+        bail!("auto 模式需要 --threshold-percentile 或 --keep-largest");
+    }
+    // glob 模式的选择规则同样在扫描前校验
+    if (args.ask_mode == AskMode::Glob) == args.select.is_empty() {
+        bail!("--ask-mode glob 与 --select 需要同时指定");
+    }
+    crate::build_select_globs(&args.select)?;
+    // 并行清理时各组同时删除，无法按累计删除量控制速率
+    if args.clean_order != CleanOrder::Serial && args.limit_rate.is_some() {
+        bail!("--limit-rate 只能与 --clean-order serial 一起使用");
+    }
+    validate_cargo_args(&args.cargo_args).map_err(|e| anyhow!(e))?;
+    validate_output(args)?;
+    Ok(())
+}
+
+/// 校验报告相关参数：--output 需要报告格式；报告写到标准输出时不能混入其他文字输出
+fn validate_output(args: &Args) -> Result<()> {
+    if args.output.is_some() && args.output_format() == OutputFormat::Human {
+        bail!("--output 需要配合 --output-format 指定报告格式");
+    }
+    if args.stdout_report().is_none() {
+        return Ok(());
+    }
+    let conflicts = [
+        (args.stats_only, "--stats-only"),
+        (
+            args.list_only_clean_candidates,
+            "--list-only-clean-candidates",
+        ),
+        (args.compare_threshold.is_some(), "--compare-threshold"),
+        (args.verbose > 0, "--verbose"),
+    ];
+    if let Some((_, flag)) = conflicts.iter().find(|(given, _)| *given) {
+        bail!(
+            "报告写到标准输出时不能使用 {}，可用 --output 写入文件",
+            flag
+        );
+    }
+    Ok(())
+}
+
+/// --help-examples 输出的常用参数组合示例
+pub const HELP_EXAMPLES: &str = "\
+常用示例:
+
+  # 扫描当前目录，遍历时逐个询问是否清理
+  clean_cargo_projects
+
+  # 不询问，直接清理 ~/code 下 target 超过 500MB 的所有项目
+  clean_cargo_projects --path ~/code --ask-mode auto --threshold-percentile 0 --min-size 500MB --yes
+
+  # 只看占用：按大小列出 ~/code 下的项目与总大小，不清理
+  clean_cargo_projects --path ~/code --stats-only
+
+  # 扫描结束后在复选框列表中勾选最大的 20 个项目中要清理的
+  clean_cargo_projects --path ~/code --ask-mode tui --sort size --max-results 20
+
+  # 保留最大的 3 个项目，预览 auto 模式会清理其余哪些项目
+  clean_cargo_projects --path ~/code --list-only-clean-candidates --keep-largest 3
+
+  # 清理 30 天内没有构建过的项目，移入回收站而不是直接删除
+  clean_cargo_projects --path ~/code --ask-mode auto --threshold-percentile 0 --older-than 30d --method rmdir --trash
+
+  # 只删除增量编译缓存，保留依赖的构建产物
+  clean_cargo_projects --path ~/code --incremental-only
+
+  # 同时清理 Node 项目的 node_modules，跳过 vendor 目录
+  clean_cargo_projects --path ~/code --detect rust --detect node --exclude vendor
+
+  # 预览模式：照常选择，只列出将释放的空间，不执行清理
+  clean_cargo_projects --path ~/code --ask-mode menu --dry-run
+
+  # 定时任务中使用：不输出遍历过程，结果导出为 CSV
+  clean_cargo_projects --path ~/code --ask-mode auto --keep-largest 5 --yes --quiet --csv report.csv
+";
+
+/// 询问用户是否清理的方式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AskMode {
+    /// 遍历时对每个找到的项目逐个询问
+    RealTime,
+    /// 遍历结束后在菜单中输入文字过滤并勾选项目
+    Menu,
+    /// 遍历结束后在复选框列表中勾选项目（指定 --threshold-percentile 时预先勾选）
+    Tui,
+    /// 遍历结束后按阈值自动选择，不询问
+    Auto,
+    /// 遍历结束后选中路径匹配 --select 的项目，不询问
+    Glob,
+}
+
+/// 遍历结束后清理选中项目的顺序
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CleanOrder {
+    /// 逐个清理
+    Serial,
+    /// 同时清理多个项目（并发数为 CPU 核数）
+    Parallel,
+    /// 按构建目录所在的设备分组，组内逐个清理，各组同时进行
+    PerDisk,
+}
+
+/// 清理 target 的方式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CleanMethod {
+    /// 执行 cargo clean
+    Cargo,
+    /// 直接删除 target 目录
+    Rmdir,
+}
+
+/// 遍历结束后项目列表的排序方式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    /// 按遍历顺序，由浅到深
+    Depth,
+    /// 按 target 大小，从大到小
+    Size,
+    /// 按路径字典序
+    Path,
+    /// 按 target 中最新文件的修改时间，从旧到新
+    Mtime,
+}
+
+/// 报告格式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 面向终端的文字输出
+    Human,
+    /// 一个 JSON 对象：`{"projects":[...],"summary":{...}}`
+    Json,
+    /// 每个项目一行 JSON，最后一行为汇总
+    JsonLines,
+    /// 每个项目一行的 CSV，带表头
+    Csv,
+    /// GitHub 风格的 Markdown 表格，附合计与生成时间
+    Markdown,
+}
+
+/// 何时使用彩色输出
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    /// 输出到终端且未设置 NO_COLOR 时着色
+    Auto,
+    /// 总是着色
+    Always,
+    /// 从不着色
+    Never,
+}
+
+/// 主排序键相同的项目之间的排序方式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecondarySortKey {
+    /// 按路径字典序
+    Path,
+    /// 按 target 大小，从大到小
+    Size,
+}
+
+/// 不在 git 仓库中的项目的处理方式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonGit {
+    /// 照常处理
+    Include,
+    /// 跳过
+    Skip,
+}
+
+/// 已有其他实例持有锁时的处理方式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnLock {
+    /// 等待其他实例结束后继续
+    Wait,
+    /// 报错退出
+    Abort,
+    /// 忽略锁，直接继续
+    Ignore,
+}
+
+/// 校验大小参数（如 "500MB"），换算为字节数要等到 `--units` 确定后，见 [`Args::min_size_bytes`] 等
+fn parse_size_arg(s: &str) -> Result<String, String> {
+    match crate::parse_size(s, SizeUnits::Binary) {
+        Some(_) => Ok(s.to_string()),
+        None => Err(format!("无法解析大小: {}", s)),
+    }
+}
+
+/// 解析时长参数（如 "30d"）
+fn parse_duration_arg(s: &str) -> Result<Duration, String> {
+    crate::parse_duration(s).ok_or_else(|| format!("无法解析时长: {}", s))
+}
+
+/// 校验自定义清理命令可以被拆分为非空的参数列表
+fn parse_command(s: &str) -> Result<String, String> {
+    match crate::split_command(s) {
+        Some(argv) if !argv.is_empty() => Ok(s.to_string()),
+        Some(_) => Err(String::from("清理命令不能为空")),
+        None => Err(format!("清理命令中的引号未闭合: {}", s)),
+    }
+}
+
+/// 解析秒数参数
+fn parse_secs(s: &str) -> Result<Duration, String> {
+    s.trim()
+        .parse()
+        .map(Duration::from_secs)
+        .map_err(|_| format!("无法解析秒数: {}", s))
+}
+
+/// 校验速率参数（如 "50MB/s" 或 "50MB"）
+fn parse_rate(s: &str) -> Result<String, String> {
+    match rate_size(s, SizeUnits::Binary) {
+        Some(_) => Ok(s.to_string()),
+        None => Err(format!("无法解析速率: {}", s)),
+    }
+}
+
+/// 将速率参数换算为字节/秒
+fn rate_size(s: &str, units: SizeUnits) -> Option<u64> {
+    let size = s.trim().strip_suffix("/s").unwrap_or(s);
+    crate::parse_size(size, units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        Args::try_parse_from(std::iter::once("clean_cargo_projects").chain(args.iter().copied()))
+            .unwrap()
+    }
+
+    #[test]
+    fn auto_mode_requires_selection_rule() {
+        assert!(validate_args(&parse(&["--ask-mode", "auto"])).is_err());
+        assert!(validate_args(&parse(&["--list-only-clean-candidates"])).is_err());
+        assert!(validate_args(&parse(&["--ask-mode", "auto", "--keep-largest", "2"])).is_ok());
+        assert!(validate_args(&parse(&[
+            "--ask-mode",
+            "auto",
+            "--threshold-percentile",
+            "80"
+        ]))
+        .is_ok());
+        // 只统计时不会用到选择规则
+        assert!(validate_args(&parse(&["--ask-mode", "auto", "--stats-only"])).is_ok());
+    }
+
+    #[test]
+    fn glob_mode_and_select_go_together() {
+        assert!(validate_args(&parse(&["--ask-mode", "glob"])).is_err());
+        assert!(validate_args(&parse(&["--select", "**/old-*"])).is_err());
+        assert!(validate_args(&parse(&["--ask-mode", "glob", "--select", "**/old-*"])).is_ok());
+        assert!(validate_args(&parse(&["--ask-mode", "glob", "--select", "[oops"])).is_err());
+    }
+
+    #[test]
+    fn no_color_is_an_alias_for_color_never() {
+        assert_eq!(parse(&[]).color_mode(), ColorMode::Auto);
+        assert_eq!(parse(&["--no-color"]).color_mode(), ColorMode::Never);
+        assert_eq!(
+            parse(&["--color", "always"]).color_mode(),
+            ColorMode::Always
+        );
+        assert!(
+            Args::try_parse_from(["clean_cargo_projects", "--no-color", "--color", "always"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parallel_clean_order_rejects_rate_limit() {
+        assert!(validate_args(&parse(&["--limit-rate", "50MB/s"])).is_ok());
+        let args = parse(&["--clean-order", "per-disk", "--limit-rate", "50MB/s"]);
+        assert!(validate_args(&args).is_err());
+    }
+
+    #[test]
+    fn cargo_args_are_allowlisted() {
+        assert!(parse_cargo_arg("--config").is_err());
+        assert!(parse_cargo_arg("--config=build.target-dir=\"/\"").is_err());
+        assert!(parse_cargo_arg("-Zunstable-options").is_err());
+        assert!(parse_cargo_arg("--target-dir=/").is_err());
+        let args = parse(&["--cargo-arg=--profile", "--cargo-arg=dev", "--cargo-arg=-v"]);
+        assert!(validate_args(&args).is_ok());
+        assert!(validate_args(&parse(&["--cargo-arg=--profile"])).is_err());
+        assert!(validate_args(&parse(&["--cargo-arg=dev"])).is_err());
+    }
+
+    #[test]
+    fn output_format_aliases_and_destinations() {
+        let args = parse(&["--json"]);
+        assert_eq!(args.stdout_report(), Some(OutputFormat::Json));
+        let args = parse(&["--json-lines", "--csv", "a.csv"]);
+        assert_eq!(args.stdout_report(), Some(OutputFormat::JsonLines));
+        assert_eq!(args.reports().len(), 2);
+        // 写入文件时照常运行，不占用标准输出
+        let args = parse(&["--output-format", "csv", "--output", "a.csv"]);
+        assert_eq!(args.stdout_report(), None);
+        assert_eq!(
+            args.reports(),
+            [(OutputFormat::Csv, Some(PathBuf::from("a.csv")))]
+        );
+        assert!(
+            Args::try_parse_from(["clean_cargo_projects", "--json", "--output-format", "csv"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn stdout_report_rejects_terminal_only_modes() {
+        assert!(validate_args(&parse(&["--output", "a.csv"])).is_err());
+        assert!(validate_args(&parse(&["--output-format", "csv", "--stats-only"])).is_err());
+        assert!(validate_args(&parse(&["--json", "-v"])).is_err());
+        let args = parse(&[
+            "--output-format",
+            "csv",
+            "--output",
+            "a.csv",
+            "--stats-only",
+        ]);
+        assert!(validate_args(&args).is_ok());
+    }
+}
@@ -0,0 +1,329 @@
+use crate::cli::Args;
+use anyhow::{bail, Context, Result};
+use clap::parser::ValueSource;
+use clap::{Arg, Command, CommandFactory, Id};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+/// 配置文件名
+const CONFIG_FILE_NAME: &str = "clean_cargo_projects.toml";
+
+/// 扫描起点下的配置文件允许设置的配置项
+///
+/// 扫描起点可能是他人的仓库或下载的目录，其中的配置文件只能调整过滤与显示方式；
+/// 执行命令、跳过确认、决定清理方式等配置项只能写在 `~/.config` 下或 `--config` 指定的文件中
+const SCAN_ROOT_KEYS: &[&str] = &[
+    "sort",
+    "sort-secondary",
+    "reverse",
+    "quiet",
+    "verbose",
+    "summary-table",
+    "units",
+    "color",
+    "no-color",
+    "ascii",
+    "scan-hidden",
+    "min-depth",
+    "exclude",
+    "include",
+    "respect-gitignore",
+    "min-target-ratio",
+    "min-size",
+    "include-empty",
+    "older-than",
+];
+
+/// 默认读取的配置文件，按优先级从低到高排列：
+/// `$HOME/.config/clean_cargo_projects.toml`，然后是扫描起点下的同名文件
+///
+/// 第二项为 true 表示该文件位于扫描起点下，读取时只允许 [`SCAN_ROOT_KEYS`] 中的配置项
+pub fn default_config_files(scan_root: &Path) -> Vec<(PathBuf, bool)> {
+    let mut files = Vec::new();
+    if let Some(home) = std::env::var_os("HOME") {
+        files.push((
+            PathBuf::from(home).join(".config").join(CONFIG_FILE_NAME),
+            false,
+        ));
+    }
+    files.push((scan_root.join(CONFIG_FILE_NAME), true));
+    files
+        .into_iter()
+        .filter(|(file, _)| file.is_file())
+        .collect()
+}
+
+/// 配置文件中的一项，已转换为等价的命令行参数
+pub struct ConfigEntry {
+    /// 对应的参数
+    pub id: Id,
+    /// 等价的命令行参数（值为 false 的开关为空）
+    pub args: Vec<OsString>,
+}
+
+/// 读取配置文件，将每个配置项转换为等价的命令行参数
+///
+/// 配置项名即长参数名（`ask-mode = "menu"`，也可写作 `ask_mode`）：
+/// 布尔值为 true 时相当于给出该开关，数组相当于重复给出该参数；
+/// `scan_root` 为 true 时只允许 [`SCAN_ROOT_KEYS`] 中的配置项
+pub fn load_as_args(file: &Path, scan_root: bool) -> Result<Vec<ConfigEntry>> {
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("读取配置文件失败: {}", file.display()))?;
+    parse_config(&content, scan_root).with_context(|| format!("配置文件 {} 有误", file.display()))
+}
+
+/// 解析配置文件内容，见 [`load_as_args`]
+fn parse_config(content: &str, scan_root: bool) -> Result<Vec<ConfigEntry>> {
+    let table: toml::Table = content.parse().context("解析配置文件失败")?;
+
+    let command = Args::command();
+    let mut entries = Vec::new();
+    for (key, value) in &table {
+        let name = key.replace('_', "-");
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(name.as_str()))
+            .filter(|_| name != "config");
+        let Some(arg) = arg else {
+            bail!("未知配置项: {}", key);
+        };
+        if scan_root && !SCAN_ROOT_KEYS.contains(&name.as_str()) {
+            bail!(
+                "扫描起点下的配置文件不能设置 {}，请写在 ~/.config/{} 或 --config 指定的文件中",
+                key,
+                CONFIG_FILE_NAME
+            );
+        }
+
+        let flag = format!("--{}", name);
+        let mut args = Vec::new();
+        match value {
+            Value::Boolean(true) => args.push(flag.into()),
+            Value::Boolean(false) => {}
+            Value::Array(items) => {
+                for item in items {
+                    args.push(format!("{}={}", flag, scalar(key, item)?).into());
+                }
+            }
+            other => args.push(format!("{}={}", flag, scalar(key, other)?).into()),
+        }
+        entries.push(ConfigEntry {
+            id: arg.get_id().clone(),
+            args,
+        });
+    }
+    Ok(entries)
+}
+
+/// 从命令行参数中取出 `--no-<开关>`，返回其余参数与被关闭的开关
+///
+/// 开关只能开启，因此用 `--no-<开关>` 忽略配置文件中开启的同名开关；
+/// 本身就是参数的名称（如 `--no-cache`）不受影响
+pub fn split_negations(cli_args: Vec<OsString>) -> (Vec<OsString>, Vec<Id>) {
+    let command = Args::command();
+    let mut rest = Vec::new();
+    let mut negated = Vec::new();
+    for (i, os_arg) in cli_args.into_iter().enumerate() {
+        let switch = os_arg
+            .to_str()
+            .filter(|_| i > 0)
+            .and_then(|arg| negated_switch(&command, arg));
+        match switch {
+            Some(arg) => negated.push(arg.get_id().clone()),
+            None => rest.push(os_arg),
+        }
+    }
+    (rest, negated)
+}
+
+/// `arg` 为 `--no-<开关>` 且本身不是已有参数时，返回对应的开关
+fn negated_switch<'a>(command: &'a Command, arg: &str) -> Option<&'a Arg> {
+    let long = arg.strip_prefix("--")?;
+    let name = long.strip_prefix("no-")?;
+    if command.get_arguments().any(|a| a.get_long() == Some(long)) {
+        return None;
+    }
+    command
+        .get_arguments()
+        .find(|a| a.get_long() == Some(name) && !a.get_action().takes_values())
+}
+
+/// 合并命令行参数与各配置文件的配置项，返回用于最终解析的参数列表
+///
+/// `configs` 按优先级从低到高排列，命令行优先于所有配置文件。某个参数已在更高优先级处给出时，
+/// 低优先级中的同名配置项与和它冲突的配置项（如命令行给出 `--keep-largest` 时配置文件中的
+/// `threshold-percentile`）都被忽略；`negated` 中的开关忽略所有配置文件中的同名配置项
+pub fn merge_args(
+    cli_args: &[OsString],
+    negated: &[Id],
+    configs: Vec<Vec<ConfigEntry>>,
+) -> Result<Vec<OsString>> {
+    let command = Args::command();
+    let matches = command
+        .clone()
+        .try_get_matches_from(cli_args)
+        .context("解析命令行参数失败")?;
+    let mut taken: Vec<Id> = command
+        .get_arguments()
+        .map(|arg| arg.get_id())
+        .filter(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+        .cloned()
+        .chain(negated.iter().cloned())
+        .collect();
+
+    let mut merged: Vec<OsString> = cli_args.iter().take(1).cloned().collect();
+    for entries in configs.into_iter().rev() {
+        let mut file_ids = Vec::new();
+        for entry in entries {
+            if taken.iter().any(|id| overrides(&command, id, &entry.id)) {
+                continue;
+            }
+            merged.extend(entry.args);
+            file_ids.push(entry.id);
+        }
+        taken.extend(file_ids);
+    }
+    merged.extend(cli_args.iter().skip(1).cloned());
+    Ok(merged)
+}
+
+/// 已给出的参数 `taken` 是否使配置项 `id` 失效：两者相同或互相冲突
+fn overrides(command: &Command, taken: &Id, id: &Id) -> bool {
+    let conflicts = |a: &Id, b: &Id| {
+        command
+            .get_arguments()
+            .find(|arg| arg.get_id() == a)
+            .is_some_and(|arg| {
+                command
+                    .get_arg_conflicts_with(arg)
+                    .iter()
+                    .any(|other| other.get_id() == b)
+            })
+    };
+    taken == id || conflicts(taken, id) || conflicts(id, taken)
+}
+
+/// 将单个配置值转换为参数值
+fn scalar(key: &str, value: &Value) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(i) => Ok(i.to_string()),
+        Value::Float(f) => Ok(f.to_string()),
+        _ => bail!("配置项 {} 的值类型不受支持", key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::AskMode;
+    use clap::Parser;
+
+    const SAMPLE: &str = include_str!("../tests/fixtures/config.toml");
+
+    fn argv(args: &[&str]) -> Vec<OsString> {
+        std::iter::once("clean_cargo_projects")
+            .chain(args.iter().copied())
+            .map(OsString::from)
+            .collect()
+    }
+
+    /// 以示例配置文件为唯一配置文件，合并给定的命令行参数后解析
+    fn parse_with_sample(cli: &[&str]) -> Args {
+        let (cli_args, negated) = split_negations(argv(cli));
+        let configs = vec![parse_config(SAMPLE, false).unwrap()];
+        Args::parse_from(merge_args(&cli_args, &negated, configs).unwrap())
+    }
+
+    #[test]
+    fn sample_config_converts_to_args() {
+        let entries = parse_config(SAMPLE, false).unwrap();
+        let find = |id: &str| {
+            let entry = entries.iter().find(|e| e.id == id).unwrap();
+            entry
+                .args
+                .iter()
+                .map(|a| a.to_str().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(find("ask_mode"), ["--ask-mode=auto"]);
+        assert_eq!(find("threshold_percentile"), ["--threshold-percentile=50"]);
+        assert_eq!(find("scan_hidden"), ["--scan-hidden"]);
+        assert!(find("include_empty").is_empty());
+        assert_eq!(
+            find("exclude"),
+            ["--exclude=vendor", "--exclude=node_modules"]
+        );
+        assert_eq!(find("cargo_args"), ["--cargo-arg=--release"]);
+    }
+
+    #[test]
+    fn sample_config_applies_without_cli_args() {
+        let args = parse_with_sample(&[]);
+        assert_eq!(args.ask_mode, AskMode::Auto);
+        assert_eq!(args.threshold_percentile, Some(50));
+        assert!(args.scan_hidden);
+        assert!(!args.include_empty);
+        assert_eq!(args.exclude, ["vendor", "node_modules"]);
+    }
+
+    #[test]
+    fn cli_value_overrides_config() {
+        let args = parse_with_sample(&["--ask-mode", "menu", "--exclude", "target-old"]);
+        assert_eq!(args.ask_mode, AskMode::Menu);
+        assert_eq!(args.exclude, ["target-old"]);
+    }
+
+    #[test]
+    fn cli_arg_drops_conflicting_config_entry() {
+        let args = parse_with_sample(&["--keep-largest", "1"]);
+        assert_eq!(args.keep_largest, Some(1));
+        assert_eq!(args.threshold_percentile, None);
+    }
+
+    #[test]
+    fn cli_negation_turns_off_config_switch() {
+        let args = parse_with_sample(&["--no-scan-hidden"]);
+        assert!(!args.scan_hidden);
+        // 本身就是参数的 --no-cache 照常解析
+        let args = parse_with_sample(&["--no-cache"]);
+        assert!(args.no_cache && args.scan_hidden);
+    }
+
+    #[test]
+    fn later_config_file_overrides_earlier() {
+        let user = parse_config(SAMPLE, false).unwrap();
+        let project = parse_config("keep-largest = 3\nask-mode = \"tui\"", false).unwrap();
+        let merged = merge_args(&argv(&[]), &[], vec![user, project]).unwrap();
+        let args = Args::parse_from(merged);
+        assert_eq!(args.ask_mode, AskMode::Tui);
+        assert_eq!(args.keep_largest, Some(3));
+        assert_eq!(args.threshold_percentile, None);
+        assert!(args.scan_hidden);
+    }
+
+    #[test]
+    fn unknown_key_is_rejected() {
+        assert!(parse_config("no-such-option = 1", false).is_err());
+        assert!(parse_config("config = \"other.toml\"", false).is_err());
+    }
+
+    #[test]
+    fn scan_root_config_rejects_execution_keys() {
+        let content = "clean-command = \"rm -rf ~\"";
+        assert!(parse_config(content, false).is_ok());
+        assert!(parse_config(content, true).is_err());
+        for key in [
+            "cargo-arg = [\"--release\"]",
+            "yes = true",
+            "ask-mode = \"auto\"",
+            "method = \"rmdir\"",
+            "trash = true",
+        ] {
+            assert!(parse_config(key, true).is_err(), "{}", key);
+        }
+        let entries = parse_config("exclude = [\"vendor\"]\nmin-size = \"10MB\"", true).unwrap();
+        assert_eq!(entries.len(), 2);
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::path::Path;
+use std::rc::Rc;
+
+/// 每一层目录中的 `.gitignore` / `.ignore` 规则
+struct Layer {
+    matcher: Gitignore,
+    parent: Option<Rc<Layer>>,
+}
+
+/// 从扫描起点到当前目录逐层叠加的忽略规则
+///
+/// 内层目录的规则优先，与 git 的行为一致：内层的 `!pattern` 可以重新包含外层忽略的路径
+#[derive(Clone, Default)]
+pub struct IgnoreStack(Option<Rc<Layer>>);
+
+impl IgnoreStack {
+    /// 进入目录 `dir`，叠加其中的忽略文件（不存在时沿用上层规则）
+    ///
+    /// 忽略文件解析失败时跳过出错的规则，并将原因追加到 `warnings`
+    pub fn enter(&self, dir: &Path, warnings: &mut Vec<String>) -> IgnoreStack {
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut has_rules = false;
+        for name in [".gitignore", ".ignore"] {
+            let file = dir.join(name);
+            if file.is_file() {
+                if let Some(e) = builder.add(&file) {
+                    warnings.push(format!("解析忽略文件失败: {}: {}", file.display(), e));
+                }
+                has_rules = true;
+            }
+        }
+        if !has_rules {
+            return self.clone();
+        }
+        match builder.build() {
+            Ok(matcher) => IgnoreStack(Some(Rc::new(Layer {
+                matcher,
+                parent: self.0.clone(),
+            }))),
+            Err(e) => {
+                warnings.push(format!("解析忽略文件失败: {}: {}", dir.display(), e));
+                self.clone()
+            }
+        }
+    }
+
+    /// 判断目录是否被忽略
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let mut layer = self.0.as_deref();
+        while let Some(current) = layer {
+            match current.matcher.matched(path, true) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => layer = current.parent.as_deref(),
+            }
+        }
+        false
+    }
+}
+
+/// 由 `--exclude` / `--exclude-from` 给出的排除规则与 `--include` 给出的包含规则，
+/// 语法与 `.gitignore` 相同，相对于扫描起点：
+/// 不含 `/` 的规则匹配任意位置的同名目录，含 `/` 的规则从扫描起点开始匹配
+pub struct Excludes {
+    excludes: Gitignore,
+    /// 没有包含规则时为 None，不限制项目位置
+    includes: Option<Gitignore>,
+}
+
+impl Excludes {
+    /// 由排除规则与包含规则构建匹配器
+    ///
+    /// 位于扫描起点内的绝对路径会转换为相对于起点的锚定规则，如扫描起点为 `/home/me/code` 时
+    /// `/home/me/code/experiments` 等价于 `/experiments`
+    pub fn new(root: &Path, excludes: &[String], includes: &[String]) -> Result<Excludes> {
+        Ok(Excludes {
+            excludes: build_matcher(root, excludes, "排除")?,
+            includes: if includes.is_empty() {
+                None
+            } else {
+                Some(build_matcher(root, includes, "包含")?)
+            },
+        })
+    }
+
+    /// 判断目录是否被排除
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.excludes.matched(path, true).is_ignore()
+    }
+
+    /// 判断位于 `path` 的项目是否应当处理：没有被排除，且给出包含规则时至少匹配其中一条
+    ///
+    /// 包含规则匹配项目目录或其任一上级目录（扫描起点以内），如 `/work/*` 包含 `work/a/b`；
+    /// 同时匹配包含与排除规则时以排除为准
+    pub fn matches_filters(&self, path: &Path) -> bool {
+        if self.is_excluded(path) {
+            return false;
+        }
+        match &self.includes {
+            None => true,
+            Some(includes) => includes.matched_path_or_any_parents(path, true).is_ignore(),
+        }
+    }
+}
+
+/// 构建相对于扫描起点的规则匹配器，`kind` 用于错误信息
+fn build_matcher(root: &Path, patterns: &[String], kind: &str) -> Result<Gitignore> {
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        // 绝对路径可能经由起点本身（可能是符号链接）或其规范化路径写出，两者都需识别
+        let relative = [root, canonical_root.as_path()]
+            .into_iter()
+            .find_map(|prefix| Path::new(pattern).strip_prefix(prefix).ok())
+            .filter(|rest| !rest.as_os_str().is_empty());
+        let pattern = match relative {
+            Some(rest) => format!("/{}", rest.to_string_lossy()),
+            None => pattern.clone(),
+        };
+        builder
+            .add_line(None, &pattern)
+            .with_context(|| format!("无效的{}规则: {}", kind, pattern))?;
+    }
+    builder
+        .build()
+        .with_context(|| format!("构建{}规则失败", kind))
+}
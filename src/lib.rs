@@ -0,0 +1,26 @@
+//! 扫描 Cargo 项目（以及 `--detect` 支持的其他项目）并统计构建目录大小的核心逻辑
+//!
+//! 命令行程序在此基础上实现询问与清理；库本身不向标准输出打印任何内容，
+//! 扫描进度与警告经由 [`walk`] 的回调交给调用方处理
+
+pub mod ignore_rules;
+mod project;
+mod scanner;
+mod size;
+pub mod size_cache;
+
+pub use project::{profile_dir_name, project_layout, CargoProject, CleanProfile, ProjectKind};
+pub use scanner::{scan, walk, ScanEvent, ScanOptions};
+pub use size::{format_bytes, get_dir_size, measure_dir_size, DirSize, SizeUnits};
+
+use std::path::PathBuf;
+
+/// 本程序的缓存目录：`$XDG_CACHE_HOME/clean_cargo_projects`，
+/// 未设置时使用 `~/.cache`（Windows 上为 `%LOCALAPPDATA%`）
+pub fn cache_dir() -> Option<PathBuf> {
+    let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+    Some(cache_dir.join("clean_cargo_projects"))
+}
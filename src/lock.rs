@@ -0,0 +1,129 @@
+use crate::cli::OnLock;
+use anyhow::{anyhow, Context, Result};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 当前实例持有的锁文件，退出（包括 Ctrl-C）时关闭以释放锁
+static HELD_LOCK: Mutex<Option<File>> = Mutex::new(None);
+
+/// 持有期间占用锁文件，离开作用域时释放
+pub struct LockGuard;
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        release();
+    }
+}
+
+/// 锁文件路径：`<缓存目录>/lock`
+fn lock_path() -> Option<PathBuf> {
+    Some(clean_cargo_projects::cache_dir()?.join("lock"))
+}
+
+/// 一次加锁尝试的结果
+enum Attempt {
+    /// 成功锁定锁文件
+    Acquired(File),
+    /// 锁已被占用，附带持有者的 PID（无法读取时为 None）
+    Held(Option<u32>),
+}
+
+/// 尝试对锁文件加系统级的独占锁，成功后写入本进程 PID
+///
+/// 锁由操作系统随文件句柄一起释放，持有者异常退出时不会留下残留锁，
+/// 因此锁文件本身不删除，各实例始终锁定同一个文件
+fn try_lock(path: &Path) -> Result<Attempt> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .with_context(|| format!("打开锁文件失败: {}", path.display()))?;
+    match file.try_lock() {
+        Ok(()) => {
+            file.set_len(0)
+                .and_then(|()| write!(file, "{}", std::process::id()))
+                .with_context(|| format!("写入锁文件失败: {}", path.display()))?;
+            Ok(Attempt::Acquired(file))
+        }
+        Err(TryLockError::WouldBlock) => {
+            let mut content = String::new();
+            let holder = file
+                .read_to_string(&mut content)
+                .ok()
+                .and_then(|_| content.trim().parse().ok());
+            Ok(Attempt::Held(holder))
+        }
+        Err(TryLockError::Error(e)) => {
+            Err(e).with_context(|| format!("锁定锁文件失败: {}", path.display()))
+        }
+    }
+}
+
+/// 获取全局锁，防止多个实例同时清理
+///
+/// 锁已被其他实例持有时按 `on_lock` 处理。
+/// 返回 None 表示未持有锁（`ignore` 模式下锁被占用，或无法确定锁文件位置）
+pub fn acquire(on_lock: OnLock) -> Result<Option<LockGuard>> {
+    let Some(path) = lock_path() else {
+        println!("[警告] 无法确定缓存目录，跳过实例锁");
+        return Ok(None);
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("创建缓存目录失败: {}", dir.display()))?;
+    }
+
+    let mut waiting = false;
+    loop {
+        let holder = match try_lock(&path)? {
+            Attempt::Acquired(file) => {
+                if let Ok(mut held) = HELD_LOCK.lock() {
+                    *held = Some(file);
+                }
+                return Ok(Some(LockGuard));
+            }
+            Attempt::Held(holder) => holder,
+        };
+        // 对方刚加锁还未写入 PID 时读不到，只是无法在提示中显示
+        let holder_str = holder
+            .map(|pid| pid.to_string())
+            .unwrap_or_else(|| String::from("未知"));
+
+        match on_lock {
+            OnLock::Abort => {
+                return Err(anyhow!(
+                    "另一个实例 (PID {}) 正在运行，锁文件: {}",
+                    holder_str,
+                    path.display()
+                ));
+            }
+            OnLock::Ignore => {
+                println!(
+                    "[警告] 另一个实例 (PID {}) 正在运行，忽略锁继续执行",
+                    holder_str
+                );
+                return Ok(None);
+            }
+            OnLock::Wait => {
+                if !waiting {
+                    println!("另一个实例 (PID {}) 正在运行，等待其结束...", holder_str);
+                    waiting = true;
+                }
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        }
+    }
+}
+
+/// 释放本实例持有的锁（未持有时不做任何事）
+pub fn release() {
+    if let Ok(mut held) = HELD_LOCK.lock() {
+        // 关闭文件句柄即释放锁
+        held.take();
+    }
+}
@@ -1,14 +1,298 @@
+mod cli;
+mod config;
+mod lock;
+mod report;
+
 use anyhow::{anyhow, Context, Result};
-use dialoguer::Input;
-use std::collections::VecDeque;
+use clap::{CommandFactory, Parser};
+use clean_cargo_projects::size_cache::SizeCache;
+use clean_cargo_projects::{
+    format_bytes, get_dir_size, profile_dir_name, project_layout, walk, CargoProject, CleanProfile,
+    ProjectKind, ScanEvent, ScanOptions, SizeUnits,
+};
+use cli::{
+    Args, AskMode, CleanMethod, CleanOrder, ColorMode, NonGit, OutputFormat, SecondarySortKey,
+    SortKey,
+};
+use console::Style;
+use dialoguer::{Confirm, FuzzySelect, Input, MultiSelect};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::IsTerminal;
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// 是否把装饰符号换成 ASCII 字符（--ascii，或关闭彩色输出时）
+static ASCII_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// 装饰符号及其 ASCII 替代；带变体选择符的写法在前，保证整体被替换
+const ASCII_GLYPHS: &[(&str, &str)] = &[
+    ("⚠️", "!"),
+    ("⏭️", ">>"),
+    ("⏳", "*"),
+    ("✓", "+"),
+    ("✔", "+"),
+    ("✗", "x"),
+    ("○", "-"),
+    ("→", "->"),
+    ("⚠", "!"),
+    ("⏭", ">>"),
+    ("🔒", "#"),
+    ("⏱", "@"),
+    ("★", "*"),
+    ("▲", "^"),
+    ("▼", "v"),
+    ("📄", "-"),
+    ("└", "`"),
+    ("─", "-"),
+    ("≥", ">="),
+];
+
+/// 开启 ASCII 输出时把装饰符号换成 ASCII 字符，部分 Windows 终端无法显示这些符号
+fn plain_glyphs(text: &str) -> Cow<'_, str> {
+    if !ASCII_OUTPUT.load(Ordering::Relaxed) {
+        return Cow::Borrowed(text);
+    }
+    let mut text = text.to_string();
+    for (glyph, ascii) in ASCII_GLYPHS {
+        text = text.replace(glyph, ascii);
+    }
+    Cow::Owned(text)
+}
+
+/// 输出一行，其中的装饰符号按 --ascii 替换
+macro_rules! say {
+    ($($arg:tt)*) => {
+        println!("{}", plain_glyphs(&format!($($arg)*)))
+    };
+}
+
+/// 按 --color（或 --no-color）、NO_COLOR 与 --ascii 决定输出样式
+fn init_output_style(args: &Args) {
+    // 按 https://no-color.org 的约定，NO_COLOR 为非空值时才生效
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let color = args.color_mode();
+    let colors = match color {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => !no_color_env && std::io::stdout().is_terminal(),
+    };
+    console::set_colors_enabled(colors);
+    // 输出重定向到文件时不着色，但仍保留符号；只有明确关闭彩色输出时才改用 ASCII
+    let colors_off = color == ColorMode::Never || (color == ColorMode::Auto && no_color_env);
+    ASCII_OUTPUT.store(args.ascii || colors_off, Ordering::Relaxed);
+}
+
+/// 解析可读的大小字符串（如 "500MB", "1.5GiB", "100 MB", "1,5GB", "1024"），忽略空白，逗号视为小数点
+///
+/// KiB、MiB 等总是按 1024 进制；KB、MB 等与输出一致，`--units si` 时按 1000 进制，否则按 1024 进制。
+/// 无法解析时返回 None
+fn parse_size(s: &str, units: SizeUnits) -> Option<u64> {
+    let s: String = s
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| if c == ',' { '.' } else { c })
+        .collect();
+    let s = s.to_uppercase();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    // KB、MB 等随 --units 变化的进制
+    let kb: u64 = if units == SizeUnits::Si { 1000 } else { 1024 };
+    let (base, exponent): (u64, u32) = match unit {
+        "" | "B" => (1, 0),
+        "KB" | "K" => (kb, 1),
+        "MB" | "M" => (kb, 2),
+        "GB" | "G" => (kb, 3),
+        "TB" | "T" => (kb, 4),
+        "KIB" => (1024, 1),
+        "MIB" => (1024, 2),
+        "GIB" => (1024, 3),
+        "TIB" => (1024, 4),
+        _ => return None,
+    };
+    Some((number * base.pow(exponent) as f64) as u64)
+}
+
+/// 解析时长字符串（如 "30d", "12h", "2w"），单位为 h（小时）、d（天）、w（周）
+///
+/// 无法解析时返回 None
+fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim().to_lowercase();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let seconds: u64 = match unit {
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(number * seconds as f64).ok()
+}
+
+/// target 目录与 Cargo.toml 的归属校验结果
+#[derive(Debug, PartialEq, Eq)]
+enum ManifestCheck {
+    /// 在 target 中找到了与包名对应的构建产物
+    Matched,
+    /// target 中有构建产物，但没有与包名对应的
+    Mismatched(String),
+    /// 无法判断（无 [package]、无法解析，或没有 debug/release 产物）
+    Unknown,
+}
+
+/// 项目目录的 git 工作区状态
+#[derive(Debug, PartialEq, Eq)]
+enum GitState {
+    /// 没有未提交的修改
+    Clean,
+    /// 有修改或未跟踪的文件
+    Dirty,
+    /// 不在 git 仓库中
+    NotGit,
+}
+
+/// 在项目目录执行 `git status --porcelain`，只检查项目目录内除构建目录外的文件
+fn git_state(project: &CargoProject) -> Result<GitState> {
+    let output = Command::new("git")
+        .args(["status", "--porcelain", "--", "."])
+        .arg(format!(":(exclude){}", project_layout(project.kind).1))
+        .current_dir(&project.path)
+        .output()
+        .context("执行 git status 失败")?;
+    if output.status.success() {
+        return Ok(if output.stdout.is_empty() {
+            GitState::Clean
+        } else {
+            GitState::Dirty
+        });
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("not a git repository") {
+        return Ok(GitState::NotGit);
+    }
+    Err(anyhow!("git status 失败: {}", stderr.trim()))
+}
+
+/// 读取 Cargo.toml 中的包名（`[package] name`）
+fn read_package_name(cargo_toml: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(cargo_toml).ok()?;
+    let manifest: toml::Table = content.parse().ok()?;
+    manifest
+        .get("package")?
+        .get("name")?
+        .as_str()
+        .map(String::from)
+}
+
+/// 校验 target 目录确实由该 Cargo.toml 构建产生
+///
+/// 在 `target/{debug,release}` 的 `.fingerprint` 与 `deps` 中查找以包名开头的条目
+fn check_manifest_owner(project_dir: &Path) -> ManifestCheck {
+    let Some(name) = read_package_name(&project_dir.join("Cargo.toml")) else {
+        return ManifestCheck::Unknown;
+    };
+    let crate_name = name.replace('-', "_");
+    let prefixes = [
+        format!("{}-", name),
+        format!("{}-", crate_name),
+        format!("lib{}-", crate_name),
+    ];
+
+    let mut has_artifacts = false;
+    for profile in ["debug", "release"] {
+        let profile_dir = project_dir.join("target").join(profile);
+        for sub in [".fingerprint", "deps"] {
+            let Ok(entries) = std::fs::read_dir(profile_dir.join(sub)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                has_artifacts = true;
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                if prefixes.iter().any(|prefix| file_name.starts_with(prefix)) {
+                    return ManifestCheck::Matched;
+                }
+            }
+        }
+    }
 
-fn get_parent_dir() -> Result<PathBuf> {
-    // 获取当前可执行文件所在目录
-    let exe_path = std::env::current_exe()
-        .context("获取当前程序路径失败")?;
+    if has_artifacts {
+        ManifestCheck::Mismatched(name)
+    } else {
+        ManifestCheck::Unknown
+    }
+}
+
+/// 格式化 target/源码 比例（如 "12.3x"）
+fn format_ratio(ratio: f64) -> String {
+    if ratio.is_infinite() {
+        String::from("∞")
+    } else {
+        format!("{:.1}x", ratio)
+    }
+}
+
+/// 删除速率限制器：按累计删除字节数控制 cargo clean 的派发节奏
+#[derive(Debug)]
+struct RateLimiter {
+    /// 速率上限（字节/秒），None 表示不限速
+    limit: Option<u64>,
+    /// 第一次清理开始的时间
+    started: Option<Instant>,
+    /// 已删除的字节数
+    bytes: u64,
+}
+
+impl RateLimiter {
+    fn new(limit: Option<u64>) -> Self {
+        Self {
+            limit,
+            started: None,
+            bytes: 0,
+        }
+    }
+
+    /// 开始一次清理前调用，记录计时起点
+    fn begin(&mut self) {
+        self.started.get_or_insert_with(Instant::now);
+    }
+
+    /// 记录一次清理删除的字节数，必要时等待以使累计删除速率不超过上限
+    fn record(&mut self, bytes: u64) {
+        self.bytes += bytes;
+        let (Some(started), Some(limit)) = (self.started, self.limit.filter(|&l| l > 0)) else {
+            return;
+        };
+        let expected = Duration::from_secs_f64(self.bytes as f64 / limit as f64);
+        let elapsed = started.elapsed();
+        if expected > elapsed {
+            let wait = expected - elapsed;
+            say!("  → [限速] 等待 {:.1} 秒...", wait.as_secs_f64());
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// 自第一次清理开始以来的平均删除速率（字节/秒）
+    fn average_rate(&self) -> Option<f64> {
+        let elapsed = self.started?.elapsed().as_secs_f64();
+        (elapsed > 0.0).then(|| self.bytes as f64 / elapsed)
+    }
+}
+
+/// 当前可执行文件所在目录（`--use-exe-dir`）
+fn exe_dir() -> Result<PathBuf> {
+    let exe_path = std::env::current_exe().context("获取当前程序路径失败")?;
     let parent_dir = exe_path
         .parent()
         .ok_or_else(|| anyhow!("无法获取父目录"))?
@@ -16,8 +300,26 @@ fn get_parent_dir() -> Result<PathBuf> {
     Ok(parent_dir)
 }
 
-fn ask_and_clean(cargo_dir: &Path) -> Result<String> {
-    println!("\n{}", "─".repeat(50));
+/// 未指定 --path 时的扫描起点：默认为当前工作目录；
+/// 给出 `--use-exe-dir` 时为程序所在目录，无法确定时回退到当前工作目录
+fn default_scan_dir(use_exe_dir: bool) -> Result<PathBuf> {
+    if use_exe_dir {
+        match exe_dir() {
+            Ok(dir) => return Ok(dir),
+            Err(e) => println!("无法确定程序所在目录（{}），使用当前工作目录", e),
+        }
+    }
+    std::env::current_dir().context("获取当前工作目录失败")
+}
+
+fn ask_and_clean(
+    project: &CargoProject,
+    args: &Args,
+    stats: &mut TraverseStats,
+    limiter: &mut RateLimiter,
+) -> Result<String> {
+    let cargo_dir = project.path.as_path();
+    say!("\n{}", "─".repeat(50));
     println!("找到 Cargo 项目: {}", cargo_dir.display());
 
     loop {
@@ -28,36 +330,33 @@ fn ask_and_clean(cargo_dir: &Path) -> Result<String> {
 
         match response.trim().to_lowercase().as_str() {
             "y" => {
-                println!("\n正在执行 cargo clean...");
-                match execute_cargo_clean(cargo_dir) {
-                    Ok(_) => {
-                        println!("✓ 清理成功: {}", cargo_dir.display());
-                        std::thread::sleep(Duration::from_secs(1));
-                    }
-                    Err(e) => {
-                        println!("✗ 清理失败: {}", e);
-                        println!("  → 继续处理下一个...");
-                        std::thread::sleep(Duration::from_secs(1));
+                if !args.dry_run {
+                    println!("\n正在执行 cargo clean...");
+                }
+                if !clean_project(project, args, stats, limiter) {
+                    if !args.fail_fast {
+                        say!("  → 继续处理下一个...");
                     }
+                    return Ok("failed".to_string());
                 }
+                std::thread::sleep(Duration::from_secs(1));
                 return Ok("cleaned".to_string());
             }
             "n" => {
-                println!("  → 跳过: {}", cargo_dir.file_name().unwrap_or_default().to_string_lossy());
+                say!(
+                    "  → 跳过: {}",
+                    cargo_dir.file_name().unwrap_or_default().to_string_lossy()
+                );
                 return Ok("skipped".to_string());
             }
             "s" => {
-                println!("\n[全部是模式] 正在执行 cargo clean...");
-                match execute_cargo_clean(cargo_dir) {
-                    Ok(_) => {
-                        println!("✓ 清理成功: {}", cargo_dir.display());
-                        std::thread::sleep(Duration::from_secs(1));
-                    }
-                    Err(e) => {
-                        println!("✗ 清理失败: {}", e);
-                        std::thread::sleep(Duration::from_secs(1));
-                    }
+                if !args.dry_run {
+                    println!("\n[全部是模式] 正在执行 cargo clean...");
+                }
+                if !clean_project(project, args, stats, limiter) {
+                    return Ok("failed".to_string());
                 }
+                std::thread::sleep(Duration::from_secs(1));
                 return Ok("cleaned".to_string());
             }
             "q" => {
@@ -71,143 +370,2156 @@ fn ask_and_clean(cargo_dir: &Path) -> Result<String> {
     }
 }
 
-fn execute_cargo_clean(cargo_dir: &Path) -> Result<()> {
-    let status = Command::new("cargo")
-        .args(&["clean"])
-        .current_dir(cargo_dir)
-        .status()
-        .with_context(|| format!("执行 cargo clean 失败: {}", cargo_dir.display()))?;
+/// 对项目执行 cargo clean（或仅删除 incremental 缓存）并输出结果，返回是否成功
+fn clean_project(
+    project: &CargoProject,
+    args: &Args,
+    stats: &mut TraverseStats,
+    limiter: &mut RateLimiter,
+) -> bool {
+    let _cleaning = CleaningGuard::new();
+
+    // 预览模式只报告将释放的空间，不删除任何文件
+    if args.dry_run {
+        let bytes = if args.incremental_only {
+            project_incremental_size(project)
+        } else {
+            project.target_bytes.unwrap_or(0)
+        };
+        println!(
+            "[预览] 将清理: {} ({})",
+            project.path.display(),
+            format_bytes(bytes, args.units)
+        );
+        if args.verbose > 0 && !args.incremental_only {
+            print_clean_dir_breakdown(project, args.clean_profile, args.units);
+        }
+        stats.freed.push((project.path.clone(), bytes));
+        stats.record_clean(&project.path, false, None);
+        return true;
+    }
+
+    if args.incremental_only {
+        return clean_incremental(project, args.units, stats, limiter);
+    }
+
+    limiter.begin();
+    let clean_started = Instant::now();
+    let profile_args = match args.clean_profile {
+        CleanProfile::All => &[][..],
+        CleanProfile::Debug => &["--profile", "dev"][..],
+        CleanProfile::Release => &["--release"][..],
+    };
+    let extra_args: Vec<String> = profile_args
+        .iter()
+        .map(|arg| arg.to_string())
+        .chain(args.cargo_args.iter().cloned())
+        .collect();
+    let mut attempts = 1;
+    let result = loop {
+        let attempt = match (&args.clean_command, args.method) {
+            (Some(template), _) => {
+                execute_clean_command(&project.path, template, args.clean_timeout).map(|_| false)
+            }
+            // 非 Rust 项目没有对应的清理命令，直接删除构建目录；
+            // 工作区成员中的 cargo clean 会作用于工作区的 target，同样直接删除成员自带的 target
+            (None, CleanMethod::Cargo)
+                if project.kind == ProjectKind::Rust && project.workspace_root.is_none() =>
+            {
+                execute_cargo_clean(&project.path, &extra_args, args.clean_timeout).map(|_| false)
+            }
+            (None, _) => remove_target(project, args.clean_profile, args.trash),
+        };
+        match attempt {
+            // 文件可能被杀毒软件或 rust-analyzer 暂时占用，按指数退避重试；收到 Ctrl-C 后不再重试
+            Err(e) if attempts <= args.retries && !stop_requested() => {
+                let delay = Duration::from_millis(500) * 2u32.saturating_pow(attempts - 1);
+                say!("✗ 第 {} 次清理失败: {}，{:.1?} 后重试", attempts, e, delay);
+                if !sleep_unless_stopped(delay) {
+                    say!("  → 收到 Ctrl-C，不再重试");
+                    break Err(e);
+                }
+                attempts += 1;
+            }
+            result => break result,
+        }
+    };
+    stats.record_attempts(&project.path, attempts);
+    stats.record_clean_time(&project.path, clean_started.elapsed());
+
+    match result {
+        Ok(trashed) => {
+            let mut freed = project.target_bytes.unwrap_or(0);
+            if args.verify {
+                let remaining = remaining_target_size(project, args.clean_profile);
+                stats.record_remaining(&project.path, remaining);
+                if remaining > VERIFY_TOLERANCE {
+                    say!(
+                        "⚠️ [警告] 清理后 target/ 仍残留 {}: {}",
+                        format_bytes(remaining, args.units),
+                        project.path.display()
+                    );
+                }
+                freed = freed.saturating_sub(remaining);
+            }
+            say!(
+                "✓ 清理成功: {} (释放 {})",
+                project.path.display(),
+                format_bytes(freed, args.units)
+            );
+            if trashed {
+                say!("  └── 已移入回收站，可从中恢复");
+                stats.record_trashed(&project.path);
+            }
+            stats.freed.push((project.path.clone(), freed));
+            stats.record_clean(&project.path, true, None);
+            limiter.record(freed);
+            true
+        }
+        Err(e) => {
+            if attempts > 1 {
+                say!("✗ 清理失败（已尝试 {} 次）: {}", attempts, e);
+            } else {
+                say!("✗ 清理失败: {}", e);
+            }
+            stats.record_clean(&project.path, false, Some(format!("{:#}", e)));
+            false
+        }
+    }
+}
+
+/// 只删除项目 target 下的 incremental 缓存目录，返回是否全部删除成功
+fn clean_incremental(
+    project: &CargoProject,
+    units: SizeUnits,
+    stats: &mut TraverseStats,
+    limiter: &mut RateLimiter,
+) -> bool {
+    limiter.begin();
+    let clean_started = Instant::now();
+    let mut freed = 0u64;
+    let mut errors = Vec::new();
+    for dir in find_incremental_dirs(&project.build_dir()) {
+        let bytes = get_dir_size(&dir, &[]).unwrap_or(0);
+        match std::fs::remove_dir_all(&dir) {
+            Ok(_) => freed += bytes,
+            Err(e) => {
+                say!("✗ 删除失败: {}: {}", dir.display(), e);
+                errors.push(format!("{}: {}", dir.display(), e));
+            }
+        }
+    }
+    say!(
+        "✓ 已删除 incremental 缓存: {} (释放 {})",
+        project.path.display(),
+        format_bytes(freed, units)
+    );
+    stats.freed.push((project.path.clone(), freed));
+    stats.record_clean_time(&project.path, clean_started.elapsed());
+    let success = errors.is_empty();
+    stats.record_clean(
+        &project.path,
+        success,
+        (!success).then(|| errors.join("; ")),
+    );
+    limiter.record(freed);
+    success
+}
+
+/// 菜单模式：输入文字模糊过滤项目，回车切换选中状态，选择“完成”后返回选中的项目路径
+///
+/// 按 Esc 取消时返回空列表
+fn menu_select(projects: &[CargoProject]) -> Result<Vec<PathBuf>> {
+    let mut selected = vec![false; projects.len()];
+
+    loop {
+        let count = selected.iter().filter(|&&s| s).count();
+        let mut items =
+            vec![plain_glyphs(&format!("✔ 完成选择（已选 {} 个）", count)).into_owned()];
+        items.extend(projects.iter().zip(&selected).map(|(project, &checked)| {
+            format!(
+                "[{}] {} ({})",
+                if checked { "x" } else { " " },
+                project.path.display(),
+                project.target_size
+            )
+        }));
+
+        let choice = FuzzySelect::new()
+            .with_prompt("输入以过滤项目，回车切换选中")
+            .items(&items)
+            .default(0)
+            .interact_opt()
+            .context("获取用户输入失败")?;
+
+        match choice {
+            Some(0) => break,
+            Some(index) => selected[index - 1] = !selected[index - 1],
+            None => {
+                println!("\n用户取消选择");
+                return Ok(Vec::new());
+            }
+        }
+    }
+
+    Ok(projects
+        .iter()
+        .zip(&selected)
+        .filter(|(_, &checked)| checked)
+        .map(|(project, _)| project.path.clone())
+        .collect())
+}
+
+/// 计算大小分布中第 `percentile` 百分位（最近秩法）对应的字节数
+fn percentile_cutoff(sizes: &[u64], percentile: u8) -> Option<u64> {
+    if sizes.is_empty() {
+        return None;
+    }
+    let mut sorted = sizes.to_vec();
+    sorted.sort_unstable();
+    let rank = (percentile as f64 / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.saturating_sub(1).min(sorted.len() - 1)])
+}
+
+/// glob 模式：选中路径（或规范化路径）匹配任一规则的项目，不询问用户
+fn glob_select(projects: &[CargoProject], patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let globs = build_select_globs(patterns)?;
+    let selected: Vec<PathBuf> = projects
+        .iter()
+        .filter(|p| globs.is_match(&p.path) || globs.is_match(&p.canonical_path))
+        .map(|p| p.path.clone())
+        .collect();
+    println!(
+        "\n[glob] 选中匹配 --select 的 {} 个项目（共 {} 个）",
+        selected.len(),
+        projects.len()
+    );
+    Ok(selected)
+}
+
+/// 编译 --select 规则，开头的 `~/` 展开为家目录
+fn build_select_globs(patterns: &[String]) -> Result<GlobSet> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let expanded = match (pattern.strip_prefix("~/"), &home) {
+            (Some(rest), Some(home)) => home.join(rest).to_string_lossy().into_owned(),
+            _ => pattern.clone(),
+        };
+        let glob = GlobBuilder::new(&expanded)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("无效的选择规则: {}", pattern))?;
+        builder.add(glob);
+    }
+    builder.build().context("构建选择规则失败")
+}
+
+/// 自动模式：按选项自动选出要清理的项目，不询问用户
+fn auto_ask(projects: &[CargoProject], args: &Args) -> Vec<PathBuf> {
+    if let Some(keep) = args.keep_largest {
+        return keep_largest_select(projects, keep, args.units);
+    }
+
+    let Some(percentile) = args.threshold_percentile else {
+        println!("\n[警告] auto 模式需要 --threshold-percentile 或 --keep-largest，未清理任何项目");
+        return Vec::new();
+    };
+
+    let sizes: Vec<u64> = projects.iter().filter_map(|p| p.target_bytes).collect();
+    let Some(cutoff) = percentile_cutoff(&sizes, percentile) else {
+        return Vec::new();
+    };
+    let selected: Vec<PathBuf> = projects
+        .iter()
+        .filter(|p| p.target_bytes.is_some_and(|bytes| bytes >= cutoff))
+        .map(|p| p.path.clone())
+        .collect();
+
+    println!(
+        "\n[自动] 第 {} 百分位对应大小: {} ({} 字节)，选中 {} / {} 个项目",
+        percentile,
+        format_bytes(cutoff, args.units),
+        cutoff,
+        selected.len(),
+        projects.len()
+    );
+
+    // -v 时列出未选中的项目，便于确认阈值是否合适
+    if args.verbose > 0 {
+        let mut unselected: Vec<&CargoProject> = projects
+            .iter()
+            .filter(|p| !selected.contains(&p.path))
+            .collect();
+        unselected.sort_by_key(|p| std::cmp::Reverse(p.target_bytes));
+        if !unselected.is_empty() {
+            println!("[自动] 低于阈值、未选中的 {} 个项目:", unselected.len());
+        }
+        for project in unselected {
+            say!("  ○ {} ({})", project.path.display(), project.target_size);
+        }
+    }
+    selected
+}
+
+/// 保留 target 最大的 `keep` 个项目，选中其余全部项目
+fn keep_largest_select(projects: &[CargoProject], keep: usize, units: SizeUnits) -> Vec<PathBuf> {
+    let mut by_size: Vec<&CargoProject> = projects.iter().collect();
+    by_size.sort_by_key(|p| std::cmp::Reverse(p.target_bytes));
+    let (kept, rest) = by_size.split_at(keep.min(by_size.len()));
+
+    println!("\n[自动] 保留最大的 {} 个项目:", kept.len());
+    for project in kept {
+        say!("  ★ {} ({})", project.path.display(), project.target_size);
+    }
+    let total: u64 = rest.iter().filter_map(|p| p.target_bytes).sum();
+    println!(
+        "[自动] 选中其余 {} 个项目，共 {}",
+        rest.len(),
+        format_bytes(total, units)
+    );
+
+    rest.iter().map(|p| p.path.clone()).collect()
+}
+
+/// 列出 target 大小在阈值 ±`margin`% 范围内的项目，并标出阈值分界
+fn print_threshold_comparison(
+    projects: &[CargoProject],
+    threshold: u64,
+    margin: f64,
+    units: SizeUnits,
+) {
+    let lower = threshold as f64 * (1.0 - margin / 100.0);
+    let upper = threshold as f64 * (1.0 + margin / 100.0);
+    let mut near: Vec<(&CargoProject, u64)> = projects
+        .iter()
+        .filter_map(|p| p.target_bytes.map(|bytes| (p, bytes)))
+        .filter(|&(_, bytes)| (lower..=upper).contains(&(bytes as f64)))
+        .collect();
+    near.sort_by_key(|&(_, bytes)| std::cmp::Reverse(bytes));
+
+    let above = projects
+        .iter()
+        .filter(|p| p.target_bytes.is_some_and(|bytes| bytes >= threshold))
+        .count();
+    println!("\n{}", "=".repeat(60));
+    println!(
+        "[阈值对比] 阈值 {}，±{}% 范围内的项目 ({} 个):",
+        format_bytes(threshold, units),
+        margin,
+        near.len()
+    );
+
+    let print_boundary = || {
+        say!(
+            "  ────────── 阈值 {} ──────────",
+            format_bytes(threshold, units)
+        )
+    };
+    let mut boundary_printed = false;
+    for (project, bytes) in near {
+        if !boundary_printed && bytes < threshold {
+            print_boundary();
+            boundary_printed = true;
+        }
+        let diff = (bytes as f64 - threshold as f64) / threshold.max(1) as f64 * 100.0;
+        let mark = if bytes >= threshold { "▲" } else { "▼" };
+        say!(
+            "  {} {:>10}  {:>+7.1}%  {}",
+            mark,
+            format_bytes(bytes, units),
+            diff,
+            project.path.display()
+        );
+    }
+    if !boundary_printed {
+        print_boundary();
+    }
+    println!(
+        "  共 {} 个项目不低于阈值，{} 个低于阈值",
+        above,
+        projects.len() - above
+    );
+}
+
+/// 以复选框列表勾选要清理的项目
+///
+/// 指定 --threshold-percentile 时预先勾选不低于该百分位的项目
+fn tui_select(projects: &[CargoProject], args: &Args) -> Result<Vec<PathBuf>> {
+    let sizes: Vec<u64> = projects.iter().filter_map(|p| p.target_bytes).collect();
+    let cutoff = args
+        .threshold_percentile
+        .and_then(|percentile| percentile_cutoff(&sizes, percentile));
+
+    let items: Vec<String> = projects
+        .iter()
+        .map(|project| format!("{} ({})", project.path.display(), project.target_size))
+        .collect();
+    let defaults: Vec<bool> = projects
+        .iter()
+        .map(|project| {
+            cutoff.is_some_and(|cutoff| project.target_bytes.is_some_and(|b| b >= cutoff))
+        })
+        .collect();
+
+    let choice = MultiSelect::new()
+        .with_prompt("空格切换选中，回车确认，Esc 取消")
+        .items(&items)
+        .defaults(&defaults)
+        .interact_opt()
+        .context("获取用户输入失败")?;
+
+    match choice {
+        Some(indices) => Ok(indices
+            .into_iter()
+            .map(|index| projects[index].path.clone())
+            .collect()),
+        None => {
+            println!("\n用户取消选择");
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// 遍历结束后按询问方式选出要清理的项目
+fn ask_mode_handler(projects: &[CargoProject], args: &Args) -> Result<Vec<PathBuf>> {
+    match args.ask_mode {
+        AskMode::Menu => menu_select(projects),
+        AskMode::Tui => tui_select(projects, args),
+        AskMode::Auto => Ok(auto_ask(projects, args)),
+        AskMode::Glob => glob_select(projects, &args.select),
+        // 实时模式在遍历中已逐个询问
+        AskMode::RealTime => Ok(Vec::new()),
+    }
+}
+
+/// 正在运行的 cargo clean 子进程 PID，退出时据此终止残留的子进程
+static RUNNING_CHILDREN: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// 正在清理的项目数（并行清理时可能多于一个），期间第一次 Ctrl-C 只请求停止而不立即退出
+static CLEANING: AtomicUsize = AtomicUsize::new(0);
+
+/// 清理过程中收到过 Ctrl-C：当前项目完成后不再开始新的清理
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// 清理期间计入 [`CLEANING`]，离开作用域时减去
+struct CleaningGuard;
+
+impl CleaningGuard {
+    fn new() -> Self {
+        CLEANING.fetch_add(1, Ordering::SeqCst);
+        CleaningGuard
+    }
+}
+
+impl Drop for CleaningGuard {
+    fn drop(&mut self) {
+        CLEANING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 是否已因 Ctrl-C 请求停止后续清理
+fn stop_requested() -> bool {
+    STOP_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// 等待给定时长，期间收到 Ctrl-C 时提前返回 false
+fn sleep_unless_stopped(duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while !stop_requested() {
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        std::thread::sleep((deadline - now).min(Duration::from_millis(100)));
+    }
+    false
+}
+
+/// 终止所有仍在运行的 cargo clean 子进程，返回终止的数量
+///
+/// Unix 上子进程各自位于独立的进程组，按进程组发送 SIGTERM，连同 cargo 派生的进程一起终止；
+/// 其他平台上子进程与本程序共享控制台，会直接收到 Ctrl-C，这里不做额外处理
+fn kill_running_children() -> usize {
+    let children = RUNNING_CHILDREN
+        .lock()
+        .map(|children| children.clone())
+        .unwrap_or_default();
+    #[cfg(unix)]
+    for &pid in &children {
+        // SAFETY: 向以子进程 PID 为组号的进程组发送信号，不涉及内存访问
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGTERM);
+        }
+    }
+    children.len()
+}
+
+/// 在项目目录中执行 `cargo clean`，`extra_args` 会追加到命令末尾
+///
+/// 指定 `timeout` 时超时未结束的进程（连同其进程组）会被终止并回收
+fn execute_cargo_clean(
+    cargo_dir: &Path,
+    extra_args: &[String],
+    timeout: Option<Duration>,
+) -> Result<()> {
+    let mut argv = vec![String::from("cargo"), String::from("clean")];
+    argv.extend(extra_args.iter().cloned());
+    run_in_project(cargo_dir, &argv, timeout)
+}
+
+/// 在项目目录中执行 --clean-command 给出的命令，参数中的 `{dir}` 替换为项目路径
+fn execute_clean_command(
+    project_dir: &Path,
+    template: &str,
+    timeout: Option<Duration>,
+) -> Result<()> {
+    let dir = project_dir.to_string_lossy();
+    let argv: Vec<String> = split_command(template)
+        .unwrap_or_default()
+        .iter()
+        .map(|arg| arg.replace("{dir}", &dir))
+        .collect();
+    run_in_project(project_dir, &argv, timeout)
+}
+
+/// 按空白拆分命令模板，单引号或双引号内的空白不拆分（引号本身会被去掉）
+///
+/// 引号未闭合时返回 None
+fn split_command(s: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    for c in s.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            None if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Some(args)
+}
+
+/// 在项目目录中执行 `argv` 描述的命令（第一个元素为程序名）
+///
+/// 指定 `timeout` 时超时未结束的进程（连同其进程组）会被终止并回收
+fn run_in_project(project_dir: &Path, argv: &[String], timeout: Option<Duration>) -> Result<()> {
+    let (program, program_args) = argv.split_first().ok_or_else(|| anyhow!("清理命令为空"))?;
+    let label = argv.join(" ");
+    let mut command = Command::new(program);
+    command.args(program_args).current_dir(project_dir);
+    // 放入独立进程组，退出时可以整组终止
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("执行 {} 失败: {}", label, project_dir.display()))?;
+    let pid = child.id();
+    if let Ok(mut children) = RUNNING_CHILDREN.lock() {
+        children.push(pid);
+    }
+    let status = match timeout {
+        None => child.wait().map(Some),
+        Some(timeout) => wait_with_timeout(&mut child, timeout),
+    };
+    if let Ok(mut children) = RUNNING_CHILDREN.lock() {
+        children.retain(|&running| running != pid);
+    }
+    let status =
+        status.with_context(|| format!("执行 {} 失败: {}", label, project_dir.display()))?;
+    let Some(status) = status else {
+        return Err(anyhow!(
+            "{} 超时（{} 秒）",
+            label,
+            timeout.unwrap_or_default().as_secs()
+        ));
+    };
 
     if !status.success() {
-        return Err(anyhow!("cargo clean 返回非零状态"));
+        return Err(anyhow!("{} 返回非零状态", label));
     }
 
     Ok(())
 }
 
-/// 计算目录的磁盘占用大小，返回可读格式的字符串（如 "20MB", "1.2GB"）
-fn get_dir_size_str(path: &Path) -> String {
-    fn dir_size_iter(path: &Path) -> std::io::Result<u64> {
-        let mut total = 0u64;
-        if let Ok(entries) = std::fs::read_dir(path) {
-            for entry in entries.flatten() {
-                total += if entry.path().is_dir() {
-                    dir_size_iter(&entry.path())?
-                } else {
-                    entry.metadata().map(|m| m.len()).unwrap_or(0)
-                };
+/// --verify 时允许清理后残留的大小，超过时给出警告
+const VERIFY_TOLERANCE: u64 = 1024 * 1024;
+
+/// 将被清理的目录：构建目录，Rust 项目指定 profile 时为 target 下对应的子目录
+fn clean_dir(project: &CargoProject, profile: CleanProfile) -> PathBuf {
+    match profile_dir_name(profile) {
+        Some(name) if project.kind == ProjectKind::Rust => project.build_dir().join(name),
+        _ => project.build_dir(),
+    }
+}
+
+/// 预览时列出将被删除的目录（构建目录或 profile 子目录）下每一项的大小，从大到小
+fn print_clean_dir_breakdown(project: &CargoProject, profile: CleanProfile, units: SizeUnits) {
+    let Ok(entries) = std::fs::read_dir(clean_dir(project, profile)) else {
+        return;
+    };
+    let mut children: Vec<(String, u64)> = entries
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if path.is_dir() {
+                (format!("{}/", name), get_dir_size(&path, &[]).unwrap_or(0))
+            } else {
+                (name, entry.metadata().map_or(0, |m| m.len()))
+            }
+        })
+        .collect();
+    children.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+    for (name, bytes) in children {
+        say!("  └── {:>10}  {}", format_bytes(bytes, units), name);
+    }
+}
+
+/// 清理后重新统计构建目录（Rust 项目指定 profile 时为对应子目录）中残留的大小
+fn remaining_target_size(project: &CargoProject, profile: CleanProfile) -> u64 {
+    let dir = clean_dir(project, profile);
+    if dir.exists() {
+        get_dir_size(&dir, &[]).unwrap_or(0)
+    } else {
+        0
+    }
+}
+
+/// 不经过 cargo 直接删除构建目录（Rust 项目指定 profile 时只删除对应的子目录）
+///
+/// 要删除的目录是符号链接时保留链接本身，只清空其指向的目录；
+/// 链接指向项目目录自身或其上级目录时拒绝删除。
+/// `to_trash` 时移入回收站而非永久删除，返回是否全部移入了回收站
+fn remove_target(project: &CargoProject, profile: CleanProfile, to_trash: bool) -> Result<bool> {
+    let project_dir = &project.path;
+    let dir = clean_dir(project, profile);
+    let metadata = match std::fs::symlink_metadata(&dir) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e).with_context(|| format!("读取目录失败: {}", dir.display())),
+    };
+    if !metadata.file_type().is_symlink() {
+        return delete_path(&dir, to_trash);
+    }
+
+    let resolved = dir
+        .canonicalize()
+        .with_context(|| format!("解析符号链接失败: {}", dir.display()))?;
+    let project_dir = project_dir.canonicalize()?;
+    if project_dir.starts_with(&resolved) {
+        return Err(anyhow!(
+            "{} 指向项目目录自身或其上级目录 {}，拒绝删除",
+            dir.display(),
+            resolved.display()
+        ));
+    }
+    let mut trashed = to_trash;
+    for entry in std::fs::read_dir(&resolved)?.flatten() {
+        trashed &= delete_path(&entry.path(), to_trash)?;
+    }
+    Ok(trashed)
+}
+
+/// 删除文件或目录（不跟随符号链接），返回是否移入了回收站
+///
+/// `to_trash` 时先尝试移入回收站，平台不支持或移动失败时给出警告后永久删除
+fn delete_path(path: &Path, to_trash: bool) -> Result<bool> {
+    if to_trash {
+        match trash::delete(path) {
+            Ok(()) => return Ok(true),
+            Err(e) => println!(
+                "[警告] 无法移入回收站（{}），改为永久删除: {}",
+                e,
+                path.display()
+            ),
+        }
+    }
+    let removed = if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    removed.with_context(|| format!("删除失败: {}", path.display()))?;
+    Ok(false)
+}
+
+/// 等待子进程结束，超时则终止其进程组并回收，返回 None
+fn wait_with_timeout(
+    child: &mut std::process::Child,
+    timeout: Duration,
+) -> std::io::Result<Option<std::process::ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            #[cfg(unix)]
+            // SAFETY: 向以子进程 PID 为组号的进程组发送信号，不涉及内存访问
+            unsafe {
+                libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
             }
+            let _ = child.kill();
+            child.wait()?;
+            return Ok(None);
         }
-        Ok(total)
+        std::thread::sleep(Duration::from_millis(100));
     }
+}
 
-    match dir_size_iter(path) {
-        Ok(bytes) => {
-            const KB: u64 = 1024;
-            const MB: u64 = KB * 1024;
-            const GB: u64 = MB * 1024;
+/// 遍历过程中的逐项输出，指定 --quiet 时不打印
+macro_rules! progress {
+    ($args:expr, $($arg:tt)*) => {
+        if !$args.quiet && $args.stdout_report().is_none() {
+            say!($($arg)*);
+        }
+    };
+}
 
-            if bytes >= GB {
-                format!("{:.1}GB", bytes as f64 / GB as f64)
-            } else if bytes >= MB {
-                format!("{:.1}MB", bytes as f64 / MB as f64)
-            } else if bytes >= KB {
-                format!("{:.1}KB", bytes as f64 / KB as f64)
-            } else {
-                format!("{}B", bytes)
+/// 查找 target 下的 incremental 缓存目录
+///
+/// 包括 `target/<profile>/incremental` 与交叉编译时的 `target/<triple>/<profile>/incremental`
+fn find_incremental_dirs(target_dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut search = vec![target_dir.to_path_buf()];
+    for _ in 0..2 {
+        let mut next = Vec::new();
+        for dir in search {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten().filter(|e| e.path().is_dir()) {
+                let incremental = entry.path().join("incremental");
+                if incremental.is_dir() {
+                    found.push(incremental);
+                } else {
+                    next.push(entry.path());
+                }
             }
         }
-        Err(_) => String::from("?"), // 无法计算时显示问号
+        search = next;
+    }
+    found
+}
+
+/// 项目中 incremental 缓存的总大小（字节），非 Rust 项目没有 incremental 缓存
+fn project_incremental_size(project: &CargoProject) -> u64 {
+    match project.kind {
+        ProjectKind::Rust => incremental_size(&project.build_dir()),
+        _ => 0,
+    }
+}
+
+/// 计算 target 下所有 incremental 缓存的总大小（字节）
+fn incremental_size(target_dir: &Path) -> u64 {
+    find_incremental_dirs(target_dir)
+        .iter()
+        .filter_map(|dir| get_dir_size(dir, &[]).ok())
+        .sum()
+}
+
+/// 目录中的文件数（递归，不跟随符号链接）
+fn count_files(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => count_files(&entry.path()),
+            Ok(_) => 1,
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// 项目构建目录中的文件数，统计大小时已遍历过整个构建目录则直接使用其结果
+fn project_file_count(project: &CargoProject) -> u64 {
+    match &project.build_dir_stats {
+        Some(stats) => stats.files,
+        None => count_files(&project.build_dir()),
+    }
+}
+
+/// 项目构建目录中最新文件的修改时间，统计大小时已遍历过整个构建目录则直接使用其结果
+fn project_newest_mtime(project: &CargoProject) -> Option<SystemTime> {
+    match &project.build_dir_stats {
+        Some(stats) => stats.newest_mtime,
+        None => newest_mtime(&project.build_dir()),
+    }
+}
+
+/// 目录中最新文件的修改时间（递归，不跟随符号链接）
+fn newest_mtime(path: &Path) -> Option<SystemTime> {
+    let mut newest = None;
+    for entry in std::fs::read_dir(path).ok()?.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let mtime = if file_type.is_dir() {
+            newest_mtime(&entry.path())
+        } else {
+            entry.metadata().and_then(|m| m.modified()).ok()
+        };
+        newest = newest.max(mtime);
+    }
+    newest
+}
+
+/// 判断路径所在的文件系统是否以只读方式挂载
+#[cfg(unix)]
+fn is_read_only_fs(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: statvfs 只写入传入的结构体，路径为有效的 C 字符串
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    let ret = unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) };
+    ret == 0 && (stat.f_flag & libc::ST_RDONLY) != 0
+}
+
+/// 非 Unix 平台暂不检测只读挂载
+#[cfg(not(unix))]
+fn is_read_only_fs(_path: &Path) -> bool {
+    false
+}
+
+/// 单个项目的处理结果，用于导出 CSV
+#[derive(Debug, Clone)]
+struct ProjectRecord {
+    path: PathBuf,
+    target_size: String,
+    target_bytes: Option<u64>,
+    /// 项目目录相对扫描起点的深度
+    depth: usize,
+    /// 是否被选中清理
+    selected: bool,
+    /// 是否清理成功
+    cleaned: bool,
+    /// 清理失败时的错误信息
+    error: Option<String>,
+    /// 执行 cargo clean 的次数（含重试）
+    attempts: u32,
+    /// --verify 时清理后 target 中残留的字节数
+    remaining_bytes: Option<u64>,
+    /// 是否移入了回收站（--trash）而非永久删除
+    trashed: bool,
+    /// 统计 target 大小的耗时
+    measure_time: Duration,
+    /// 清理（含重试）的耗时，未清理时为 None
+    clean_time: Option<Duration>,
+}
+
+impl ProjectRecord {
+    fn new(project: &CargoProject) -> Self {
+        Self {
+            path: project.path.clone(),
+            target_size: project.target_size.clone(),
+            target_bytes: project.target_bytes,
+            depth: project.depth,
+            selected: false,
+            cleaned: false,
+            error: None,
+            attempts: 0,
+            remaining_bytes: None,
+            trashed: false,
+            measure_time: project.measure_time,
+            clean_time: None,
+        }
     }
 }
 
-fn traverse_and_clean(parent_dir: &Path) -> Result<(usize, usize)> {
-    let mut cleaned = 0;
-    let mut skipped = 0;
+/// 遍历与清理的统计结果
+#[derive(Debug, Default)]
+struct TraverseStats {
+    /// 已清理的项目数
+    cleaned: usize,
+    /// 跳过的项目数
+    skipped: usize,
+    /// 找到的项目总数（含被过滤的）
+    found: usize,
+    /// 因 target 为空而忽略的项目数
+    empty: usize,
+    /// 因 target 小于 --min-size 而忽略的项目数
+    too_small: usize,
+    /// 因 target 近期有修改（未达到 --older-than）而跳过的项目数
+    too_fresh: usize,
+    /// 因有未提交的修改（--only-clean-git-clean）而跳过的项目数
+    git_dirty: usize,
+    /// 因不在 git 仓库中（--non-git skip）而跳过的项目数
+    non_git: usize,
+    /// 因受保护而未清理的项目
+    protected: Vec<PathBuf>,
+    /// 因位于只读文件系统而跳过的项目
+    read_only: Vec<PathBuf>,
+    /// 每个清理成功的项目及其释放的字节数
+    freed: Vec<(PathBuf, u64)>,
+    /// 指定限速时的实际平均删除速率（字节/秒）
+    average_rate: Option<f64>,
+    /// 通过过滤条件的每个项目的处理结果
+    records: Vec<ProjectRecord>,
+    /// 遍历时无法读取的目录及其错误，这些目录下的项目未被扫描
+    scan_errors: Vec<(PathBuf, std::io::Error)>,
+}
 
-    // 使用 VecDeque 作为队列实现BFS遍历
-    let mut dir_queue: VecDeque<(PathBuf, usize)> = VecDeque::new();
-    dir_queue.push_back((parent_dir.to_path_buf(), 0));
+impl TraverseStats {
+    /// 已释放的精确总字节数，显示时再统一格式化
+    fn freed_bytes(&self) -> u64 {
+        self.freed.iter().map(|(_, bytes)| bytes).sum()
+    }
 
-    while let Some((current_dir, depth)) = dir_queue.pop_front() {
-        let indent = "  ".repeat(depth);
+    /// 记录项目被选中清理的结果
+    fn record_clean(&mut self, path: &Path, cleaned: bool, error: Option<String>) {
+        if let Some(record) = self.records.iter_mut().find(|r| r.path == path) {
+            record.selected = true;
+            record.cleaned = cleaned;
+            record.error = error;
+        }
+    }
 
-        // 打印当前正在遍历的目录
-        if let Some(dir_name) = current_dir.file_name() {
-            println!("{}⏳ [遍历] {}/", indent, dir_name.to_string_lossy());
+    /// 记录清理后 target 中残留的字节数
+    fn record_remaining(&mut self, path: &Path, remaining: u64) {
+        if let Some(record) = self.records.iter_mut().find(|r| r.path == path) {
+            record.remaining_bytes = Some(remaining);
         }
+    }
 
-        // 检查是否有 Cargo.toml 且 target 目录存在
-        let cargo_toml = current_dir.join("Cargo.toml");
-        let target_dir = current_dir.join("target");
-        if cargo_toml.exists() && target_dir.exists() {
-            let target_size = get_dir_size_str(&target_dir);
-            println!("{}  └── ✓ 找到 Cargo.toml + target/ ({})", indent, target_size);
+    /// 记录清理的耗时
+    fn record_clean_time(&mut self, path: &Path, elapsed: Duration) {
+        if let Some(record) = self.records.iter_mut().find(|r| r.path == path) {
+            record.clean_time = Some(elapsed);
+        }
+    }
 
-            match ask_and_clean(&current_dir) {
-                Ok(action) => {
-                    if action == "cleaned" {
-                        cleaned += 1;
-                    } else if action == "skipped" {
-                        skipped += 1;
-                    } else if action == "quit" {
-                        println!("\n[中止] 用户选择退出");
-                        return Ok((cleaned, skipped));
+    /// 记录构建目录已移入回收站
+    fn record_trashed(&mut self, path: &Path) {
+        if let Some(record) = self.records.iter_mut().find(|r| r.path == path) {
+            record.trashed = true;
+        }
+    }
+
+    /// 记录执行 cargo clean 的次数
+    fn record_attempts(&mut self, path: &Path, attempts: u32) {
+        if let Some(record) = self.records.iter_mut().find(|r| r.path == path) {
+            record.attempts = attempts;
+        }
+    }
+}
+
+/// 按选项检查找到的项目是否为待清理候选，并输出找到的信息
+fn check_project(
+    project: &CargoProject,
+    indent: &str,
+    args: &Args,
+    protected: &HashSet<PathBuf>,
+    stats: &mut TraverseStats,
+) -> bool {
+    // 受保护的项目无论其他选项如何都不清理
+    if protected.contains(&project.canonical_path) {
+        progress!(
+            args,
+            "{}  └── 🔒 [保护] 受保护的项目，不会清理: {}",
+            indent,
+            project.path.display()
+        );
+        stats.protected.push(project.path.clone());
+        stats.skipped += 1;
+        return false;
+    }
+
+    // 空 target 没有可回收的空间，默认直接忽略
+    if project.target_bytes == Some(0) && !args.include_empty {
+        stats.empty += 1;
+        return false;
+    }
+
+    // 小于 --min-size 的 target 不值得清理，同样不列出；--include-empty 列出的空 target 也不例外
+    if let (Some(min_size), Some(bytes)) = (args.min_size_bytes(), project.target_bytes) {
+        if bytes < min_size {
+            stats.too_small += 1;
+            return false;
+        }
+    }
+
+    // 只读文件系统上的清理必然失败，直接跳过
+    if is_read_only_fs(&project.build_dir()) {
+        progress!(
+            args,
+            "{}  └── ⏭️ [跳过] 位于只读文件系统: {}",
+            indent,
+            project.path.display()
+        );
+        stats.read_only.push(project.path.clone());
+        stats.skipped += 1;
+        return false;
+    }
+
+    // 校验 target 是否属于该 Cargo.toml，不匹配时不清理
+    if args.verify_manifest && project.kind == ProjectKind::Rust {
+        if let ManifestCheck::Mismatched(name) = check_manifest_owner(&project.path) {
+            progress!(
+                args,
+                "{}  └── ⚠️ [跳过] target/ 中未找到包 `{}` 的构建产物，可能不属于该项目: {}",
+                indent,
+                name,
+                project.path.display()
+            );
+            stats.skipped += 1;
+            return false;
+        }
+    }
+
+    let mut details = vec![project.target_size.clone()];
+
+    // 仅在指定 --min-target-ratio 时才额外计算源码大小
+    let ratio = args.min_target_ratio.map(|_| project.target_ratio());
+    if let Some(ratio) = ratio {
+        let ratio_str = ratio.map(format_ratio).unwrap_or_else(|| String::from("?"));
+        details.push(format!("比例 {}", ratio_str));
+    }
+
+    // 仅清理 incremental 缓存时显示其大小
+    let incremental_bytes = args
+        .incremental_only
+        .then(|| project_incremental_size(project));
+    if let Some(bytes) = incremental_bytes {
+        details.push(format!("incremental {}", format_bytes(bytes, args.units)));
+    }
+
+    progress!(
+        args,
+        "{}  └── ✓ 找到 {} + {}/ ({})",
+        indent,
+        project_layout(project.kind).0,
+        project_layout(project.kind).1,
+        details.join(", ")
+    );
+
+    if let (Some(min_ratio), Some(ratio)) = (args.min_target_ratio, ratio) {
+        if ratio.is_none_or(|ratio| ratio < min_ratio) {
+            progress!(
+                args,
+                "{}  └── ⏭️ [跳过] target/源码 比例低于 {}",
+                indent,
+                format_ratio(min_ratio)
+            );
+            stats.skipped += 1;
+            return false;
+        }
+    }
+
+    if incremental_bytes == Some(0) {
+        progress!(args, "{}  └── ⏭️ [跳过] 没有 incremental 缓存", indent);
+        stats.skipped += 1;
+        return false;
+    }
+
+    // 近期构建过的项目仍在使用，不清理（修改时间在未来也视为近期）
+    if let Some(older_than) = args.older_than {
+        let fresh = project_newest_mtime(project)
+            .is_some_and(|mtime| mtime.elapsed().map_or(true, |age| age < older_than));
+        if fresh {
+            progress!(args, "{}  └── ⏭️ [跳过] target/ 近期有修改", indent);
+            stats.too_fresh += 1;
+            stats.skipped += 1;
+            return false;
+        }
+    }
+
+    // 正在编辑的项目（有未提交的修改）不清理
+    if args.only_clean_git_clean {
+        match git_state(project) {
+            Ok(GitState::Clean) => {}
+            Ok(GitState::Dirty) => {
+                progress!(args, "{}  └── ⏭️ [跳过] 有未提交的修改", indent);
+                stats.git_dirty += 1;
+                stats.skipped += 1;
+                return false;
+            }
+            Ok(GitState::NotGit) if args.non_git == NonGit::Skip => {
+                progress!(args, "{}  └── ⏭️ [跳过] 不在 git 仓库中", indent);
+                stats.non_git += 1;
+                stats.skipped += 1;
+                return false;
+            }
+            Ok(GitState::NotGit) => {}
+            Err(e) => {
+                progress!(args, "{}  └── ⚠️ [跳过] 无法检查 git 状态: {:#}", indent, e);
+                stats.skipped += 1;
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// 实时模式下询问并清理单个项目
+///
+/// 返回 `true` 表示用户选择退出
+fn ask_project(
+    project: &CargoProject,
+    indent: &str,
+    args: &Args,
+    stats: &mut TraverseStats,
+    limiter: &mut RateLimiter,
+) -> bool {
+    match ask_and_clean(project, args, stats, limiter) {
+        Ok(action) => {
+            if action == "cleaned" {
+                stats.cleaned += 1;
+            } else if action == "skipped" {
+                stats.skipped += 1;
+            } else if action == "failed" && args.fail_fast {
+                println!("\n[中止] 清理失败，--fail-fast 停止后续清理");
+                return true;
+            } else if action == "quit" {
+                println!("\n[中止] 用户选择退出");
+                return true;
+            }
+        }
+        Err(e) => {
+            say!("{}  └── ✗ 操作出错: {}", indent, e);
+            stats.skipped += 1;
+        }
+    }
+    if stop_requested() {
+        println!("\n[中止] 收到 Ctrl-C，停止后续清理");
+        return true;
+    }
+    false
+}
+
+/// 构建目录有子项无法读取时提示大小不完整；-v 时输出统计项目大小的耗时，-vv 时再输出构建目录中的文件数
+fn print_measure_details(project: &CargoProject, indent: &str, args: &Args) {
+    if project.unreadable_entries > 0 {
+        progress!(
+            args,
+            "{}  └── ⚠️ {}/ 中有 {} 项无法读取，实际大小可能更大",
+            indent,
+            project_layout(project.kind).1,
+            project.unreadable_entries
+        );
+    }
+    if args.verbose > 0 {
+        say!(
+            "{}  └── ⏱ 统计 target 大小耗时 {:.2?}",
+            indent,
+            project.measure_time
+        );
+    }
+    if args.verbose > 1 {
+        say!(
+            "{}  └── {}/ 中共 {} 个文件",
+            indent,
+            project_layout(project.kind).1,
+            project_file_count(project)
+        );
+    }
+}
+
+fn traverse_and_clean(roots: &[PathBuf], args: &Args) -> Result<TraverseStats> {
+    let mut stats = TraverseStats::default();
+    let scan_started = Instant::now();
+    let mut limiter = RateLimiter::new(args.limit_rate_bytes());
+    let protected = match &args.protect_file {
+        Some(file) => load_protected_paths(file)?,
+        None => HashSet::new(),
+    };
+    let mut exclude_patterns = args.exclude.clone();
+    for file in &args.exclude_from {
+        exclude_patterns.extend(load_exclude_patterns(file)?);
+    }
+    let mut size_cache = if args.no_cache {
+        SizeCache::disabled()
+    } else {
+        SizeCache::load()
+    };
+    let options = ScanOptions {
+        detect: args.detect.clone(),
+        profile: args.clean_profile,
+        min_depth: args.min_depth,
+        scan_hidden: args.scan_hidden,
+        follow_symlinks: args.follow_symlinks,
+        scan_inside_target: args.scan_inside_target,
+        respect_gitignore: args.respect_gitignore,
+        exclude: exclude_patterns,
+        include: args.include.clone(),
+        units: args.units,
+    };
+    // 非实时模式（或阈值对比、候选预览）下先收集所有候选项目，遍历结束后统一处理
+    let mut candidates: Vec<CargoProject> = Vec::new();
+
+    let mut on_event = |event: ScanEvent<'_>| {
+        match event {
+            ScanEvent::Enter { dir, depth } => {
+                // 打印当前正在遍历的目录
+                let indent = "  ".repeat(depth);
+                if args.verbose > 0 {
+                    progress!(args, "{}⏳ [遍历] {}", indent, dir.display());
+                } else if let Some(dir_name) = dir.file_name() {
+                    progress!(args, "{}⏳ [遍历] {}/", indent, dir_name.to_string_lossy());
+                }
+            }
+            ScanEvent::Found { project, depth } => {
+                let indent = "  ".repeat(depth);
+                print_measure_details(&project, &indent, args);
+                if let Some(workspace) = &project.workspace_root {
+                    progress!(
+                        args,
+                        "{}  └── 属于工作区 {}，清理时直接删除成员自带的 target",
+                        indent,
+                        workspace.display()
+                    );
+                }
+                stats.found += 1;
+                if check_project(&project, &indent, args, &protected, &mut stats) {
+                    // 只记录通过过滤条件的项目，导出的报告与汇总不含被跳过的项目
+                    stats.records.push(ProjectRecord::new(&project));
+                    if let Some(format) = args.stdout_report() {
+                        if format == OutputFormat::JsonLines {
+                            println!("{}", report::json_found_line(stats.records.last().unwrap()));
+                        }
+                        candidates.push(project);
+                    } else if args.ask_mode != AskMode::RealTime
+                        || args.compare_threshold.is_some()
+                        || args.list_only_clean_candidates
+                        || args.stats_only
+                    {
+                        candidates.push(project);
+                    } else if ask_project(&project, &indent, args, &mut stats, &mut limiter) {
+                        return ControlFlow::Break(());
                     }
                 }
-                Err(e) => {
-                    println!("{}  └── ✗ 操作出错: {}", indent, e);
-                    skipped += 1;
+            }
+            ScanEvent::Duplicate { project, depth } => {
+                let indent = "  ".repeat(depth);
+                print_measure_details(&project, &indent, args);
+                progress!(
+                    args,
+                    "{}  └── ○ 已处理过: {}",
+                    indent,
+                    project.canonical_path.display()
+                );
+            }
+            ScanEvent::PermissionDenied { dir, depth } => {
+                progress!(
+                    args,
+                    "{}⏭️ [跳过] 无权限访问: {}",
+                    "  ".repeat(depth),
+                    dir.display()
+                );
+                stats.skipped += 1;
+                stats.scan_errors.push((
+                    dir.to_path_buf(),
+                    std::io::ErrorKind::PermissionDenied.into(),
+                ));
+            }
+            ScanEvent::ReadError { dir, depth, error } => {
+                if args.stdout_report().is_some() {
+                    eprintln!("[警告] {}", error);
+                } else {
+                    say!("{}⚠️ [警告] {}", "  ".repeat(depth), error);
+                }
+                stats.scan_errors.push((dir.to_path_buf(), error));
+            }
+            ScanEvent::Warning { message, .. } if args.stdout_report().is_some() => {
+                eprintln!("[警告] {}", message);
+            }
+            ScanEvent::Warning { message, .. } => {
+                println!("[警告] {}", message);
+            }
+        }
+        ControlFlow::Continue(())
+    };
+    match &args.from_scan {
+        // 不遍历，把保存的项目逐个当作新找到的项目处理，照常过滤、询问与清理
+        Some(file) => {
+            for project in load_scan_file(file, args, &mut size_cache)? {
+                if on_event(ScanEvent::Found { project, depth: 0 }).is_break() {
+                    break;
                 }
             }
         }
+        None => walk(roots, &options, &mut size_cache, &mut on_event)?,
+    }
+
+    if let Err(e) = size_cache.save() {
+        if args.stdout_report().is_some() {
+            eprintln!("[警告] {:#}", e);
+        } else {
+            println!("[警告] {:#}", e);
+        }
+    }
+    if args.verbose > 0 {
+        println!("[耗时] 扫描: {:.2?}", scan_started.elapsed());
+    }
+
+    // 报告写到标准输出时只输出报告，不进入选择与清理
+    match args.stdout_report() {
+        Some(OutputFormat::JsonLines) => {
+            println!("{}", report::json_summary_line(&stats));
+            return Ok(stats);
+        }
+        Some(format) => {
+            report::write_output(format, &mut std::io::stdout(), &stats, args.units)?;
+            return Ok(stats);
+        }
+        None => {}
+    }
+
+    // 只输出占用报告，不进入选择与清理
+    if args.stats_only {
+        print_stats_report(&candidates, args.units);
+        return Ok(stats);
+    }
 
-        // 收集子目录
-        match std::fs::read_dir(&current_dir) {
-            Ok(entries) => {
-                let sub_dirs: Vec<(PathBuf, usize)> = entries
-                    .filter_map(|entry| entry.ok())
-                    .filter(|e| e.path().is_dir())
-                    .map(|e| (e.path(), depth + 1))
-                    .collect();
+    // 排序与反转对后续展示与选择菜单一致生效
+    sort_projects(&mut candidates, args.sort, args.sort_secondary);
+    if args.reverse {
+        candidates.reverse();
+    }
+
+    if !candidates.is_empty() {
+        print_reclaimable_total(&candidates, args);
+    }
 
-                // BFS：直接将子目录添加到队列末尾
-                dir_queue.extend(sub_dirs);
+    // 可回收总量按全部项目统计，之后只保留前 N 个
+    if let Some(max) = args.max_results {
+        let max = max as usize;
+        if candidates.len() > max {
+            let omitted = candidates.len() - max;
+            candidates.truncate(max);
+            println!("显示前 {} 个 (还有 {} 个被省略)", max, omitted);
+            stats.skipped += omitted;
+        }
+    }
+
+    // 阈值对比只展示结果，不执行清理
+    if let Some(threshold) = args.compare_threshold_bytes() {
+        print_threshold_comparison(&candidates, threshold, args.compare_margin, args.units);
+        stats.skipped += candidates.len();
+        return Ok(stats);
+    }
+
+    // 只预览 auto 模式会选中的项目，不执行清理
+    if args.list_only_clean_candidates {
+        let selected = auto_ask(&candidates, args);
+        println!("\n[预览] auto 模式将清理以下 {} 个项目:", selected.len());
+        let preview: Vec<&CargoProject> = candidates
+            .iter()
+            .filter(|p| selected.contains(&p.path))
+            .collect();
+        print_project_table(&preview);
+        stats.skipped += candidates.len();
+        return Ok(stats);
+    }
+
+    if !candidates.is_empty() {
+        let select_started = Instant::now();
+        let mut selected = ask_mode_handler(&candidates, args)?;
+        if matches!(args.ask_mode, AskMode::Auto | AskMode::Glob)
+            && !selected.is_empty()
+            && !confirm_auto_clean(&candidates, &selected, args)?
+        {
+            println!("已取消清理");
+            selected.clear();
+        }
+        if args.verbose > 0 {
+            println!("[耗时] 选择: {:.2?}", select_started.elapsed());
+        }
+
+        let clean_started = Instant::now();
+        let aborted = AtomicBool::new(false);
+        match args.clean_order {
+            CleanOrder::Serial => {
+                let projects: Vec<&CargoProject> = candidates.iter().collect();
+                clean_selected(
+                    &projects,
+                    &selected,
+                    args,
+                    &mut stats,
+                    &mut limiter,
+                    &aborted,
+                );
             }
-            Err(ref e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
-                println!("{}⏭️ [跳过] 无权限访问: {}", indent, current_dir.display());
-                skipped += 1;
+            order => {
+                let (chosen, rest): (Vec<&CargoProject>, Vec<&CargoProject>) = candidates
+                    .iter()
+                    .partition(|project| selected.contains(&project.path));
+                stats.skipped += rest.len();
+                clean_in_groups(&chosen, order, args, &mut stats, &aborted);
             }
-            Err(e) => {
-                println!("{}⚠️ [警告] {}", indent, e);
+        }
+        if args.verbose > 0 {
+            println!("[耗时] 清理: {:.2?}", clean_started.elapsed());
+        }
+    }
+
+    if args.limit_rate.is_some() {
+        stats.average_rate = limiter.average_rate();
+    }
+    Ok(stats)
+}
+
+/// 依次清理 `projects` 中被选中的项目，未选中或已中止时计为跳过
+///
+/// 收到 Ctrl-C 或 `--fail-fast` 下清理失败时置位 `aborted`，并行清理的各组共享该标志，一并停止后续清理
+fn clean_selected(
+    projects: &[&CargoProject],
+    selected: &[PathBuf],
+    args: &Args,
+    stats: &mut TraverseStats,
+    limiter: &mut RateLimiter,
+    aborted: &AtomicBool,
+) {
+    for project in projects {
+        if stop_requested() && !aborted.swap(true, Ordering::SeqCst) {
+            println!("\n[中止] 收到 Ctrl-C，停止后续清理");
+        }
+        if selected.contains(&project.path) && !aborted.load(Ordering::SeqCst) {
+            if !args.dry_run {
+                println!("\n正在执行 cargo clean: {}", project.path.display());
+            }
+            if clean_project(project, args, stats, limiter) {
+                stats.cleaned += 1;
+            } else if args.fail_fast && !aborted.swap(true, Ordering::SeqCst) {
+                println!("\n[中止] 清理失败，--fail-fast 停止后续清理");
+            }
+        } else {
+            stats.skipped += 1;
+        }
+    }
+}
+
+/// 按 `--clean-order parallel|per-disk` 分组清理选中的项目：组内逐个清理，各组同时进行
+///
+/// parallel 每个项目单独一组；per-disk 按构建目录所在的设备分组，避免同一块磁盘上的多个清理互相争抢。
+/// 每组在各自的统计结果中记录，全部完成后合并
+fn clean_in_groups(
+    projects: &[&CargoProject],
+    order: CleanOrder,
+    args: &Args,
+    stats: &mut TraverseStats,
+    aborted: &AtomicBool,
+) {
+    let mut groups: Vec<(Option<u64>, Vec<&CargoProject>)> = Vec::new();
+    for &project in projects {
+        let key = match order {
+            CleanOrder::PerDisk => device_id(&project.build_dir()),
+            _ => None,
+        };
+        match groups.iter_mut().find(|(k, _)| key.is_some() && *k == key) {
+            Some((_, group)) => group.push(project),
+            None => groups.push((key, vec![project])),
+        }
+    }
+    if order == CleanOrder::PerDisk {
+        println!("\n按设备分为 {} 组并行清理", groups.len());
+    }
+
+    let selected: Vec<PathBuf> = projects.iter().map(|p| p.path.clone()).collect();
+    let results: Vec<TraverseStats> = groups
+        .par_iter()
+        .map(|(_, group)| {
+            let mut local = TraverseStats {
+                records: stats
+                    .records
+                    .iter()
+                    .filter(|record| group.iter().any(|p| p.path == record.path))
+                    .cloned()
+                    .collect(),
+                ..TraverseStats::default()
+            };
+            // 并行清理不支持 --limit-rate，启动时已校验
+            let mut limiter = RateLimiter::new(None);
+            clean_selected(group, &selected, args, &mut local, &mut limiter, aborted);
+            local
+        })
+        .collect();
+
+    for local in results {
+        stats.cleaned += local.cleaned;
+        stats.skipped += local.skipped;
+        stats.freed.extend(local.freed);
+        for record in local.records {
+            if let Some(slot) = stats.records.iter_mut().find(|r| r.path == record.path) {
+                *slot = record;
+            }
+        }
+    }
+}
+
+/// 路径所在的设备号，无法获取时返回 None（该项目单独成组）
+#[cfg(unix)]
+fn device_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|metadata| metadata.dev())
+}
+
+/// 非 Unix 平台上以盘符等路径前缀区分设备
+#[cfg(not(unix))]
+fn device_id(path: &Path) -> Option<u64> {
+    use std::hash::{Hash, Hasher};
+    let prefix = path.canonicalize().ok()?.components().next()?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    prefix.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// 按扫描起点下的一级目录汇总释放的空间，输出对齐的表格
+///
+/// 有多个扫描起点时目录名带上所属的起点
+fn print_summary_table(roots: &[PathBuf], freed: &[(PathBuf, u64)], units: SizeUnits) {
+    // 一级目录 -> (项目数, 释放字节数)，保持首次出现的顺序
+    let mut groups: Vec<(String, usize, u64)> = Vec::new();
+    for (path, bytes) in freed {
+        let group = roots
+            .iter()
+            .find_map(|root| {
+                let relative = path.strip_prefix(root).ok()?;
+                let group = match relative.components().next() {
+                    Some(first) => root.join(first),
+                    None => root.clone(),
+                };
+                let group = match roots.len() {
+                    1 => group.strip_prefix(root).ok()?.to_path_buf(),
+                    _ => group,
+                };
+                Some(group.to_string_lossy().into_owned())
+            })
+            .filter(|group| !group.is_empty())
+            .unwrap_or_else(|| String::from("."));
+        match groups.iter_mut().find(|(name, _, _)| *name == group) {
+            Some((_, count, total)) => {
+                *count += 1;
+                *total += bytes;
+            }
+            None => groups.push((group, 1, *bytes)),
+        }
+    }
+    groups.sort_by_key(|&(_, _, total)| std::cmp::Reverse(total));
+
+    // 数值列在前、目录名在后，避免中文表头宽度影响对齐
+    println!("\n{:>6}释放  项目数  目录", "");
+    println!("{}", "-".repeat(40));
+    for (name, count, total) in &groups {
+        println!(
+            "{:>10}  {:>6}  {}",
+            format_bytes(*total, units),
+            count,
+            name
+        );
+    }
+    let total: u64 = freed.iter().map(|(_, bytes)| bytes).sum();
+    println!("{}", "-".repeat(40));
+    println!(
+        "{:>10}  {:>6}  合计",
+        format_bytes(total, units),
+        freed.len()
+    );
+}
+
+/// 读取排除规则文件：每行一条规则，忽略空行与 `#` 开头的注释
+fn load_exclude_patterns(file: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("读取排除规则文件失败: {}", file.display()))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// 读取受保护项目列表：每行一个项目路径，忽略空行与 `#` 开头的注释
+///
+/// 路径会被规范化，以便与项目的规范化路径比较
+fn load_protected_paths(file: &Path) -> Result<HashSet<PathBuf>> {
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("读取受保护项目列表失败: {}", file.display()))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .map(|path| path.canonicalize().unwrap_or(path))
+        .collect())
+}
+
+/// 由 --path 得到扫描起点：`-` 展开为从标准输入读取的各行，未指定时使用默认起点
+fn scan_roots(args: &Args) -> Result<Vec<PathBuf>> {
+    if args.path.is_empty() {
+        if args.use_exe_dir && args.stdout_report().is_none() {
+            println!("未指定 --path，使用程序所在目录");
+        }
+        return Ok(vec![default_scan_dir(args.use_exe_dir)?]);
+    }
+    let mut roots = Vec::new();
+    for path in &args.path {
+        if path != Path::new("-") {
+            roots.push(path.clone());
+            continue;
+        }
+        for line in std::io::stdin().lines() {
+            let line = line.context("从标准输入读取扫描起点失败")?;
+            let line = line.trim();
+            if !line.is_empty() {
+                roots.push(PathBuf::from(line));
+            }
+        }
+    }
+    if roots.is_empty() {
+        return Err(anyhow!("标准输入中没有扫描起点"));
+    }
+    Ok(roots)
+}
+
+/// 校验扫描起点存在、是目录且可读取
+fn validate_scan_root(root: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(root)
+        .with_context(|| format!("扫描目录不存在或无法访问: {}", root.display()))?;
+    if !metadata.is_dir() {
+        return Err(anyhow!("扫描路径不是目录: {}", root.display()));
+    }
+    std::fs::read_dir(root).with_context(|| format!("无法读取扫描目录: {}", root.display()))?;
+    Ok(())
+}
+
+/// 按指定方式排序收集到的项目（排序稳定，相同键保持遍历顺序，指定次要键时按次要键排列）
+fn sort_projects(projects: &mut [CargoProject], key: SortKey, secondary: Option<SecondarySortKey>) {
+    // 先按次要键排序，之后按主键的稳定排序会保留主键相同的项目之间的次要顺序
+    if let Some(secondary) = secondary {
+        if key == SortKey::Depth {
+            // 遍历顺序中同一深度的项目相邻（多个扫描起点时各起点依次排列），只在每组内排序
+            for group in projects.chunk_by_mut(|a, b| a.depth == b.depth) {
+                sort_by_secondary(group, secondary);
+            }
+            return;
+        }
+        sort_by_secondary(projects, secondary);
+    }
+    match key {
+        // 广度优先遍历的发现顺序本身就是由浅到深
+        SortKey::Depth => {}
+        SortKey::Size => projects.sort_by_key(|p| std::cmp::Reverse(p.target_bytes)),
+        SortKey::Path => projects.sort_by(|a, b| a.path.cmp(&b.path)),
+        SortKey::Mtime => projects.sort_by_cached_key(project_newest_mtime),
+    }
+}
+
+/// 按次要键排序：路径按字典序，大小从大到小
+fn sort_by_secondary(projects: &mut [CargoProject], key: SecondarySortKey) {
+    match key {
+        SecondarySortKey::Path => projects.sort_by(|a, b| a.path.cmp(&b.path)),
+        SecondarySortKey::Size => projects.sort_by_key(|p| std::cmp::Reverse(p.target_bytes)),
+    }
+}
+
+/// 是否通过环境变量 CLEAN_CARGO_ASSUME_YES 跳过确认（设置为 0、false 或空值时不跳过）
+fn assume_yes_from_env() -> bool {
+    std::env::var("CLEAN_CARGO_ASSUME_YES")
+        .is_ok_and(|value| !matches!(value.trim().to_lowercase().as_str(), "" | "0" | "false"))
+}
+
+/// auto、glob 模式执行清理前的最终确认，显示将清理的项目数与总大小
+///
+/// 指定 --yes、--dry-run 或设置 CLEAN_CARGO_ASSUME_YES 时不询问
+fn confirm_auto_clean(
+    projects: &[CargoProject],
+    selected: &[PathBuf],
+    args: &Args,
+) -> Result<bool> {
+    if args.yes || args.dry_run || assume_yes_from_env() {
+        return Ok(true);
+    }
+    let total: u64 = projects
+        .iter()
+        .filter(|p| selected.contains(&p.path))
+        .filter_map(|p| p.target_bytes)
+        .sum();
+    Confirm::new()
+        .with_prompt(format!(
+            "即将清理 {} 个项目（共 {}），确认继续?",
+            selected.len(),
+            format_bytes(total, args.units)
+        ))
+        .default(false)
+        .interact()
+        .context("获取用户确认失败")
+}
+
+/// 打印所有候选项目可回收空间的总和（--incremental-only 时只计 incremental 缓存）
+fn print_reclaimable_total(projects: &[CargoProject], args: &Args) {
+    let total: u64 = projects
+        .iter()
+        .map(|project| {
+            if args.incremental_only {
+                project_incremental_size(project)
+            } else {
+                project.target_bytes.unwrap_or(0)
             }
+        })
+        .sum();
+    println!(
+        "\n总计可回收: {} 跨 {} 个项目",
+        format_bytes(total, args.units),
+        projects.len()
+    );
+}
+
+/// --stats-only 的占用报告：按大小从大到小列出项目，最后给出总大小
+fn print_stats_report(projects: &[CargoProject], units: SizeUnits) {
+    let mut sorted: Vec<&CargoProject> = projects.iter().collect();
+    sorted.sort_by_key(|project| std::cmp::Reverse(project.target_bytes));
+
+    println!();
+    print_project_table(&sorted);
+    let total: u64 = projects.iter().filter_map(|p| p.target_bytes).sum();
+    println!(
+        "{:>4}  {:>10}  合计 {} 个项目",
+        "",
+        format_bytes(total, units),
+        projects.len()
+    );
+}
+
+/// 以对齐的表格列出项目：序号、大小、深度与路径
+fn print_project_table(projects: &[&CargoProject]) {
+    // 数值列在前、路径在后，避免中文表头宽度影响对齐
+    println!("{:>4}  {:>6}大小  深度  项目", "#", "");
+    println!("{}", "-".repeat(50));
+    for (i, project) in projects.iter().enumerate() {
+        println!(
+            "{:>4}  {}  {:>4}  {}",
+            i + 1,
+            styled_size(project),
+            project.depth,
+            project.path.display()
+        );
+    }
+    println!("{}", "-".repeat(50));
+}
+
+/// 右对齐的大小列，输出到终端时按大小着色：小于 100MB 绿色，小于 1GB 黄色，其余红色
+fn styled_size(project: &CargoProject) -> String {
+    const MB: u64 = 1024 * 1024;
+    let style = match project.target_bytes.unwrap_or(0) {
+        bytes if bytes < 100 * MB => Style::new().green(),
+        bytes if bytes < 1024 * MB => Style::new().yellow(),
+        _ => Style::new().red(),
+    };
+    style
+        .apply_to(format!("{:>10}", project.target_size))
+        .to_string()
+}
+
+/// 将写入文件的报告（--output、--csv、--markdown）导出，写入失败时以退出码 1 退出
+fn export_reports(args: &Args, stats: &TraverseStats) {
+    for (format, file) in args.reports() {
+        let Some(file) = file else {
+            continue;
+        };
+        if let Err(e) = report::write_file(format, &file, stats, args.units) {
+            eprintln!("错误: {:#}", e);
+            lock::release();
+            std::process::exit(1);
         }
+        say!(
+            "  📄 已导出 {}: {}",
+            report::format_name(format),
+            file.display()
+        );
     }
+}
 
-    Ok((cleaned, skipped))
+/// 读取此前导出的扫描结果（json、json-lines 或 CSV 报告），重新统计其中仍然存在的项目
+///
+/// 项目目录或构建目录已不存在的项目会被跳过；项目类型按 --detect 重新识别
+fn load_scan_file(file: &Path, args: &Args, cache: &mut SizeCache) -> Result<Vec<CargoProject>> {
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("读取扫描结果失败: {}", file.display()))?;
+    let paths = report::read_scan_paths(&content)
+        .with_context(|| format!("{} 不是可读取的扫描结果", file.display()))?;
+
+    let mut projects = Vec::new();
+    for path in paths {
+        let kind = args.detect.iter().copied().find(|&kind| {
+            let (marker, build_dir) = project_layout(kind);
+            path.join(marker).is_file() && path.join(build_dir).is_dir()
+        });
+        match kind {
+            Some(kind) => projects.push(CargoProject::new(
+                path,
+                0,
+                kind,
+                args.clean_profile,
+                args.units,
+                cache,
+            )),
+            None if args.stdout_report().is_some() => {
+                eprintln!("[跳过] 项目或其构建目录已不存在: {}", path.display())
+            }
+            None => say!("⏭️ [跳过] 项目或其构建目录已不存在: {}", path.display()),
+        }
+    }
+    Ok(projects)
+}
+
+/// 解析命令行参数并合并配置文件（命令行中给出的参数优先于配置文件）
+fn load_args() -> Result<Args> {
+    let (cli_args, negated) = config::split_negations(std::env::args_os().collect());
+    let args = Args::parse_from(&cli_args);
+    // 输出示例或补全脚本时不读取配置文件，避免提示信息混入输出
+    if args.help_examples || args.generate_completions.is_some() {
+        return Ok(args);
+    }
+
+    let config_files = match &args.config {
+        Some(file) => vec![(file.clone(), false)],
+        None => {
+            // 多个扫描起点时读取第一个起点下的配置文件；不读取标准输入，留待解析起点时使用
+            let scan_root = match args.path.iter().find(|path| *path != Path::new("-")) {
+                Some(path) => path.clone(),
+                None => default_scan_dir(args.use_exe_dir)?,
+            };
+            config::default_config_files(&scan_root)
+        }
+    };
+    if config_files.is_empty() {
+        return Ok(args);
+    }
+
+    let mut configs = Vec::new();
+    for (file, scan_root) in &config_files {
+        if args.stdout_report().is_none() {
+            println!("读取配置文件: {}", file.display());
+        }
+        configs.push(config::load_as_args(file, *scan_root)?);
+    }
+    let merged = config::merge_args(&cli_args, &negated, configs)?;
+    Ok(Args::parse_from(merged))
 }
 
 fn main() -> Result<()> {
-    let parent_dir = get_parent_dir()?;
+    let args = match load_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("错误: {:#}", e);
+            std::process::exit(2);
+        }
+    };
+    if args.help_examples {
+        print!("{}", cli::HELP_EXAMPLES);
+        return Ok(());
+    }
+    if let Some(shell) = args.generate_completions {
+        let mut command = Args::command();
+        let name = command.get_name().to_string();
+        clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+        return Ok(());
+    }
+    init_output_style(&args);
+    let roots = match scan_roots(&args) {
+        Ok(roots) => roots,
+        Err(e) => {
+            eprintln!("错误: {:#}", e);
+            std::process::exit(2);
+        }
+    };
+
+    // 正在清理时第一次 Ctrl-C 只停止后续清理，当前项目完成后照常输出汇总；
+    // 其他时候（或再次按下）先终止正在运行的 cargo clean，避免留下孤儿进程，然后立即退出
+    ctrlc::set_handler(|| {
+        if CLEANING.load(Ordering::SeqCst) > 0 && !STOP_REQUESTED.swap(true, Ordering::SeqCst) {
+            eprintln!("\n[中止] 当前项目清理完成后停止，再次按 Ctrl-C 立即终止");
+            return;
+        }
+        let killed = kill_running_children();
+        if killed > 0 {
+            eprintln!("\n[中止] 已终止 {} 个 cargo clean 进程", killed);
+        }
+        lock::release();
+        std::process::exit(130);
+    })
+    .context("设置 Ctrl-C 处理器失败")?;
+
+    if let Err(e) = cli::validate_args(&args) {
+        eprintln!("错误: {:#}", e);
+        std::process::exit(2);
+    }
+
+    if let Err(e) = roots.iter().try_for_each(|root| validate_scan_root(root)) {
+        eprintln!("错误: {:#}", e);
+        std::process::exit(2);
+    }
+
+    // 防止多个实例同时清理同一批项目；只统计或预览、不会清理的运行以及 --no-lock 时不加锁
+    let skip_lock = args.no_lock
+        || args.stats_only
+        || args.stdout_report().is_some()
+        || args.compare_threshold.is_some()
+        || args.list_only_clean_candidates
+        || args.dry_run;
+    let _lock = if skip_lock {
+        None
+    } else {
+        match lock::acquire(args.on_lock) {
+            Ok(lock) => lock,
+            Err(e) => {
+                eprintln!("错误: {:#}", e);
+                std::process::exit(3);
+            }
+        }
+    };
 
-    println!("遍历目录: {}", parent_dir.display());
-    println!("{}", "=".repeat(60));
-    println!("提示: y=执行 clean, n=跳过, s=全部执行, q=全部退出");
-    println!("{}", "=".repeat(60));
+    if args.clear_cache {
+        match SizeCache::clear() {
+            Ok(()) if args.stdout_report().is_some() => {}
+            Ok(()) => println!("已清除大小缓存"),
+            Err(e) => eprintln!("[警告] 清除大小缓存失败: {}", e),
+        }
+    }
 
-    match traverse_and_clean(&parent_dir) {
-        Ok((cleaned, skipped)) => {
+    // 报告写到标准输出时只输出报告
+    if args.stdout_report().is_some() {
+        if let Err(e) = traverse_and_clean(&roots, &args) {
+            eprintln!("错误: {:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    match &args.from_scan {
+        Some(file) => println!("读取扫描结果: {}", file.display()),
+        None => {
+            for root in &roots {
+                println!("遍历目录: {}", root.display());
+            }
+        }
+    }
+    if !args.quiet && !args.stats_only {
+        println!("{}", "=".repeat(60));
+        println!("提示: y=执行 clean, n=跳过, s=全部执行, q=全部退出");
+        println!("{}", "=".repeat(60));
+    }
+
+    match traverse_and_clean(&roots, &args) {
+        Ok(stats) if args.stats_only => export_reports(&args, &stats),
+        Ok(stats) => {
+            let failures: Vec<&ProjectRecord> = stats
+                .records
+                .iter()
+                .filter(|record| record.error.is_some())
+                .collect();
             println!("\n{}", "=".repeat(60));
             println!("遍历完成!");
-            println!("  ✓ 清理完成: {} 个项目", cleaned);
-            println!("  ○ 跳过: {} 个项目", skipped);
+            if stats.found == 0 {
+                println!("  未找到任何 Cargo 项目");
+            }
+            if args.dry_run {
+                say!("  ✓ 将清理: {} 个项目", stats.cleaned);
+                println!(
+                    "  预览: 将释放约 {}",
+                    format_bytes(stats.freed_bytes(), args.units)
+                );
+            } else {
+                say!("  ✓ 清理完成: {} 个项目", stats.cleaned);
+                if !stats.freed.is_empty() || !failures.is_empty() {
+                    // 每个项目的释放量已扣除 --verify 时的残留
+                    println!(
+                        "  已释放: {} 跨 {} 个项目, {} 个失败",
+                        format_bytes(stats.freed_bytes(), args.units),
+                        stats.freed.len(),
+                        failures.len()
+                    );
+                }
+            }
+            say!("  ○ 跳过: {} 个项目", stats.skipped);
+            if !failures.is_empty() {
+                say!("  ✗ 清理失败: {} 个项目", failures.len());
+                for record in &failures {
+                    println!(
+                        "      - {}: {}",
+                        record.path.display(),
+                        record.error.as_deref().unwrap_or_default()
+                    );
+                }
+            }
+            if stats.empty > 0 {
+                say!("  ○ 空 target 已忽略: {} 个项目", stats.empty);
+            }
+            if stats.too_small > 0 {
+                say!("  ○ 小于最小大小已忽略: {} 个项目", stats.too_small);
+            }
+            if stats.too_fresh > 0 {
+                say!("  ○ 近期有修改而跳过: {} 个项目", stats.too_fresh);
+            }
+            if stats.git_dirty > 0 {
+                say!("  ○ 有未提交的修改而跳过: {} 个项目", stats.git_dirty);
+            }
+            if stats.non_git > 0 {
+                say!("  ○ 不在 git 仓库中而跳过: {} 个项目", stats.non_git);
+            }
+            if !stats.read_only.is_empty() {
+                say!(
+                    "  ○ 位于只读文件系统而跳过: {} 个项目",
+                    stats.read_only.len()
+                );
+                for path in &stats.read_only {
+                    println!("      - {}", path.display());
+                }
+            }
+            if !stats.protected.is_empty() {
+                say!("  🔒 受保护而未清理: {} 个项目", stats.protected.len());
+                for path in &stats.protected {
+                    println!("      - {}", path.display());
+                }
+            }
+            if !stats.scan_errors.is_empty() {
+                say!(
+                    "  ⚠️ 警告: {} 个目录无法读取，其中的项目未被扫描",
+                    stats.scan_errors.len()
+                );
+                if args.verbose > 0 {
+                    for (dir, error) in &stats.scan_errors {
+                        println!("      - {}: {}", dir.display(), error);
+                    }
+                }
+            }
+            if args.summary_table && !stats.freed.is_empty() {
+                print_summary_table(&roots, &stats.freed, args.units);
+            }
+            if let Some(rate) = stats.average_rate {
+                say!(
+                    "  ⏱ 平均删除速率: {}/s",
+                    format_bytes(rate as u64, args.units)
+                );
+            }
+            export_reports(&args, &stats);
             println!("{}", "=".repeat(60));
+
+            // 部分项目清理失败时以非零退出码结束，便于脚本检测
+            if !failures.is_empty() {
+                lock::release();
+                std::process::exit(4);
+            }
+            // 因 Ctrl-C 提前停止时与直接中断一样以 130 退出
+            if stop_requested() {
+                lock::release();
+                std::process::exit(130);
+            }
         }
         Err(e) => {
-            eprintln!("\n错误: {}", e);
+            eprintln!("\n错误: {:#}", e);
+            lock::release();
             std::process::exit(1);
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    /// 不访问磁盘、target 为给定字节数的项目
+    fn project(name: &str, bytes: u64) -> CargoProject {
+        let path = PathBuf::from("/code").join(name);
+        CargoProject {
+            canonical_path: path.clone(),
+            path,
+            depth: 1,
+            kind: ProjectKind::Rust,
+            target_bytes: Some(bytes),
+            unreadable_entries: 0,
+            target_size: format_bytes(bytes, SizeUnits::Binary),
+            measure_time: Duration::ZERO,
+            build_dir_stats: None,
+            workspace_root: None,
+        }
+    }
+
+    #[test]
+    fn auto_mode_compares_exact_bytes_not_rounded_sizes() {
+        // 约 1.49GB 的 target 显示为 "1.5GB"，与恰好 1.5GB 的阈值项目显示相同
+        let below = project("below", GIB * 149 / 100);
+        let at = project("at", GIB * 3 / 2);
+        assert_eq!(below.target_size, at.target_size);
+
+        let sizes = [
+            below.target_bytes.unwrap(),
+            at.target_bytes.unwrap(),
+            2 * GIB,
+        ];
+        assert_eq!(percentile_cutoff(&sizes, 50), Some(GIB * 3 / 2));
+
+        let projects = [below, at, project("above", 2 * GIB)];
+        let args = Args::parse_from([
+            "clean_cargo_projects",
+            "--ask-mode",
+            "auto",
+            "--threshold-percentile",
+            "50",
+        ]);
+        assert_eq!(
+            auto_ask(&projects, &args),
+            [PathBuf::from("/code/at"), PathBuf::from("/code/above")]
+        );
+    }
+
+    #[test]
+    fn freed_total_sums_exact_bytes() {
+        // 1075 字节显示为 "1.0KB"（向下取整），1100 字节显示为 "1.1KB"（向上取整）
+        assert_eq!(format_bytes(1075, SizeUnits::Binary), "1.0KB");
+        assert_eq!(format_bytes(1100, SizeUnits::Binary), "1.1KB");
+        let mut stats = TraverseStats::default();
+        for i in 0..10 {
+            stats
+                .freed
+                .push((PathBuf::from(format!("down{}", i)), 1075));
+            stats.freed.push((PathBuf::from(format!("up{}", i)), 1100));
+        }
+        // 按显示值相加为 21.0KB，精确总和为 21750 字节
+        assert_eq!(stats.freed_bytes(), 21_750);
+        assert_eq!(
+            format_bytes(stats.freed_bytes(), SizeUnits::Binary),
+            "21.2KB"
+        );
+    }
+
+    #[test]
+    fn percentile_cutoff_picks_nearest_rank() {
+        assert_eq!(percentile_cutoff(&[], 50), None);
+        assert_eq!(percentile_cutoff(&[30, 10, 20], 0), Some(10));
+        assert_eq!(percentile_cutoff(&[30, 10, 20], 34), Some(20));
+        assert_eq!(percentile_cutoff(&[30, 10, 20], 100), Some(30));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::size_cache::SizeCache;
+use crate::{format_bytes, get_dir_size, DirSize, SizeUnits};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// 识别的项目类型
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProjectKind {
+    /// 含 Cargo.toml 与 target/ 的 Cargo 项目
+    Rust,
+    /// 含 package.json 与 node_modules/ 的 Node 项目
+    Node,
+    /// 含 pyproject.toml 与 __pycache__/ 的 Python 项目
+    Python,
+}
+
+/// 清理哪个 profile 的构建产物
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CleanProfile {
+    /// 只清理 target/debug
+    Debug,
+    /// 只清理 target/release
+    Release,
+    /// 清理整个 target
+    All,
+}
+
+/// 扫描中找到的项目（默认为 Cargo 项目，`--detect` 可识别其他类型）
+///
+/// 相等性与哈希只基于规范化路径与项目类型，便于放入 `HashSet` 去重
+/// （同一项目经由符号链接等不同路径访问时视为同一个）
+#[derive(Debug, Clone)]
+pub struct CargoProject {
+    /// 遍历时访问到的项目路径
+    pub path: PathBuf,
+    /// 项目目录相对扫描起点的深度（起点为 0）
+    pub depth: usize,
+    /// 项目类型，决定构建目录与清理方式
+    pub kind: ProjectKind,
+    /// 规范化后的项目路径（无法规范化时与 `path` 相同）
+    pub canonical_path: PathBuf,
+    /// target 目录大小（字节，无法计算时为 None）
+    pub target_bytes: Option<u64>,
+    /// target 中无法读取而未计入大小的子项数，不为 0 时实际大小可能更大
+    pub unreadable_entries: u64,
+    /// target 目录大小（可读格式，大小不完整时带 "≥" 前缀）
+    pub target_size: String,
+    /// 统计 target 大小的耗时
+    pub measure_time: Duration,
+    /// 统计大小时完整遍历整个构建目录得到的结果（含文件数与最新修改时间），
+    /// 大小取自缓存或只统计了 profile 子目录时为 None
+    pub build_dir_stats: Option<DirSize>,
+    /// 项目为工作区成员时所属工作区的根目录：在成员中执行 cargo clean 只会清理工作区的 target，
+    /// 成员自带的 target 需直接删除
+    pub workspace_root: Option<PathBuf>,
+}
+
+impl CargoProject {
+    /// 统计将被清理部分的大小：Rust 项目指定 profile 时只计 target 下对应的子目录；
+    /// `target_size` 按 `units` 格式化
+    pub fn new(
+        path: PathBuf,
+        depth: usize,
+        kind: ProjectKind,
+        profile: CleanProfile,
+        units: SizeUnits,
+        cache: &mut SizeCache,
+    ) -> Self {
+        let measure_started = Instant::now();
+        let canonical_path = path.canonicalize().unwrap_or_else(|_| path.clone());
+        let build_dir = path.join(project_layout(kind).1);
+        let profile = if kind == ProjectKind::Rust {
+            profile_dir_name(profile)
+        } else {
+            None
+        };
+        let size = match profile {
+            None => cache.dir_size(&build_dir).ok(),
+            Some(name) => {
+                let dir = build_dir.join(name);
+                // 该 profile 从未构建过时没有可清理的内容
+                if dir.exists() {
+                    cache.dir_size(&dir).ok()
+                } else {
+                    Some(DirSize::default())
+                }
+            }
+        };
+        let target_bytes = size.map(|size| size.bytes);
+        let build_dir_stats = size.filter(|size| profile.is_none() && !size.from_cache);
+        let unreadable_entries = size.map_or(0, |size| size.unreadable);
+        let target_size = match target_bytes {
+            Some(bytes) if unreadable_entries > 0 => format!("≥{}", format_bytes(bytes, units)),
+            Some(bytes) => format_bytes(bytes, units),
+            None => String::from("?"),
+        };
+        Self {
+            path,
+            depth,
+            kind,
+            canonical_path,
+            target_bytes,
+            unreadable_entries,
+            target_size,
+            measure_time: measure_started.elapsed(),
+            build_dir_stats,
+            workspace_root: None,
+        }
+    }
+
+    /// 构建目录（Rust 项目为 target）
+    pub fn build_dir(&self) -> PathBuf {
+        self.path.join(project_layout(self.kind).1)
+    }
+
+    /// target 与源码（项目目录中除构建目录外的部分）的大小比例
+    ///
+    /// 源码大小为 0 时返回无穷大，任一大小无法计算时返回 None
+    pub fn target_ratio(&self) -> Option<f64> {
+        let target_bytes = self.target_bytes?;
+        let source_bytes = get_dir_size(&self.path, &[project_layout(self.kind).1]).ok()?;
+        if source_bytes == 0 {
+            return Some(f64::INFINITY);
+        }
+        Some(target_bytes as f64 / source_bytes as f64)
+    }
+}
+
+impl PartialEq for CargoProject {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical_path == other.canonical_path && self.kind == other.kind
+    }
+}
+
+impl Eq for CargoProject {}
+
+impl Hash for CargoProject {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical_path.hash(state);
+        self.kind.hash(state);
+    }
+}
+
+/// 项目类型的标志文件与构建目录名
+pub fn project_layout(kind: ProjectKind) -> (&'static str, &'static str) {
+    match kind {
+        ProjectKind::Rust => ("Cargo.toml", "target"),
+        ProjectKind::Node => ("package.json", "node_modules"),
+        ProjectKind::Python => ("pyproject.toml", "__pycache__"),
+    }
+}
+
+/// 指定 profile 时对应的 target 子目录名
+pub fn profile_dir_name(profile: CleanProfile) -> Option<&'static str> {
+    match profile {
+        CleanProfile::All => None,
+        CleanProfile::Debug => Some("debug"),
+        CleanProfile::Release => Some("release"),
+    }
+}
@@ -0,0 +1,604 @@
+use crate::cli::OutputFormat;
+use crate::{ProjectRecord, TraverseStats};
+use anyhow::{anyhow, bail, Context, Result};
+use clean_cargo_projects::{format_bytes, SizeUnits};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 报告格式的显示名称
+pub fn format_name(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Human => "文本",
+        OutputFormat::Json => "JSON",
+        OutputFormat::JsonLines => "JSON Lines",
+        OutputFormat::Csv => "CSV",
+        OutputFormat::Markdown => "Markdown",
+    }
+}
+
+/// 按格式将每个项目的处理结果写入 `out`，各格式使用同一份处理结果；合计大小按 `units` 格式化
+pub fn write_output(
+    format: OutputFormat,
+    out: &mut dyn Write,
+    stats: &TraverseStats,
+    units: SizeUnits,
+) -> Result<()> {
+    let content = match format {
+        OutputFormat::Human => human_report(&stats.records, units),
+        OutputFormat::Json => json_report(stats),
+        OutputFormat::JsonLines => json_lines_report(stats),
+        OutputFormat::Csv => csv_report(&stats.records),
+        OutputFormat::Markdown => markdown_report(&stats.records, units),
+    };
+    out.write_all(content.as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+/// 按格式将报告写入文件
+pub fn write_file(
+    format: OutputFormat,
+    file: &Path,
+    stats: &TraverseStats,
+    units: SizeUnits,
+) -> Result<()> {
+    std::fs::File::create(file)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut out| write_output(format, &mut out, stats, units))
+        .with_context(|| format!("写入{}报告失败: {}", format_name(format), file.display()))
+}
+
+/// 以对齐的纯文本列出项目：序号、大小、深度与路径，附合计
+fn human_report(records: &[ProjectRecord], units: SizeUnits) -> String {
+    // 数值列在前、路径在后，避免中文表头宽度影响对齐
+    let mut content = format!("{:>4}  {:>6}大小  深度  路径\n", "#", "");
+    for (i, record) in records.iter().enumerate() {
+        content.push_str(&format!(
+            "{:>4}  {:>10}  {:>4}  {}\n",
+            i + 1,
+            record.target_size,
+            record.depth,
+            record.path.display()
+        ));
+    }
+    content.push_str(&format!(
+        "{:>4}  {:>10}  合计 {} 个项目\n",
+        "",
+        format_bytes(total_bytes(records), units),
+        records.len()
+    ));
+    content
+}
+
+/// 各项目 target 的精确总字节数
+fn total_bytes(records: &[ProjectRecord]) -> u64 {
+    records.iter().filter_map(|r| r.target_bytes).sum()
+}
+
+/// 将字符串编码为 JSON 字符串字面量
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// 可选数值的 JSON 表示，None 为 null
+fn json_number(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| String::from("null"), |value| value.to_string())
+}
+
+/// json 报告中的一个项目
+fn json_project(record: &ProjectRecord) -> String {
+    format!(
+        "{{\"path\":{},\"target_size\":{},\"target_bytes\":{},\"depth\":{},\"selected\":{},\
+         \"cleaned\":{},\"error\":{},\"attempts\":{},\"remaining_bytes\":{},\"trashed\":{},\
+         \"measure_ms\":{},\"clean_ms\":{}}}",
+        json_string(&record.path.to_string_lossy()),
+        json_string(&record.target_size),
+        json_number(record.target_bytes),
+        record.depth,
+        record.selected,
+        record.cleaned,
+        record
+            .error
+            .as_deref()
+            .map_or_else(|| String::from("null"), json_string),
+        record.attempts,
+        json_number(record.remaining_bytes),
+        record.trashed,
+        record.measure_time.as_millis(),
+        json_number(record.clean_time.map(|t| t.as_millis()))
+    )
+}
+
+/// 汇总：找到的项目总数（含被过滤的）、报告中的项目数与其总大小、无法读取的目录数
+fn json_summary(stats: &TraverseStats) -> String {
+    format!(
+        "\"found\":{},\"listed\":{},\"total_bytes\":{},\"scan_errors\":{}",
+        stats.found,
+        stats.records.len(),
+        total_bytes(&stats.records),
+        stats.scan_errors.len()
+    )
+}
+
+/// json 报告：`{"projects":[...],"summary":{...}}`，每个项目占一行
+fn json_report(stats: &TraverseStats) -> String {
+    let projects: Vec<String> = stats
+        .records
+        .iter()
+        .map(|record| format!("\n  {}", json_project(record)))
+        .collect();
+    let newline = if projects.is_empty() { "" } else { "\n" };
+    format!(
+        "{{\"projects\":[{}{}],\"summary\":{{{}}}}}\n",
+        projects.join(","),
+        newline,
+        json_summary(stats)
+    )
+}
+
+/// json-lines 报告中找到一个项目时的一行
+pub fn json_found_line(record: &ProjectRecord) -> String {
+    format!(
+        "{{\"event\":\"found\",\"path\":{},\"target_size\":{},\"target_bytes\":{},\"depth\":{}}}",
+        json_string(&record.path.to_string_lossy()),
+        json_string(&record.target_size),
+        json_number(record.target_bytes),
+        record.depth
+    )
+}
+
+/// json-lines 报告结束时的汇总行
+pub fn json_summary_line(stats: &TraverseStats) -> String {
+    format!("{{\"event\":\"summary\",{}}}", json_summary(stats))
+}
+
+fn json_lines_report(stats: &TraverseStats) -> String {
+    let mut content = String::new();
+    for record in &stats.records {
+        content.push_str(&json_found_line(record));
+        content.push('\n');
+    }
+    content.push_str(&json_summary_line(stats));
+    content.push('\n');
+    content
+}
+
+/// 解析后的 JSON 值，只用于读取本程序输出的 json 与 json-lines 报告
+#[derive(Debug, PartialEq)]
+enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// 对象中名为 `key` 的字段
+    fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// 解析一个完整的 JSON 值，其后只允许空白
+fn parse_json(content: &str) -> Result<JsonValue> {
+    let mut chars = content.chars().peekable();
+    let value = parse_json_value(&mut chars)?;
+    skip_json_whitespace(&mut chars);
+    match chars.next() {
+        None => Ok(value),
+        Some(c) => bail!("JSON 值之后有多余的字符 {:?}", c),
+    }
+}
+
+type JsonChars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn skip_json_whitespace(chars: &mut JsonChars) {
+    while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+}
+
+fn expect_json_char(chars: &mut JsonChars, expected: char) -> Result<()> {
+    skip_json_whitespace(chars);
+    match chars.next() {
+        Some(c) if c == expected => Ok(()),
+        Some(c) => bail!("JSON 中应为 {:?}，实际为 {:?}", expected, c),
+        None => bail!("JSON 意外结束，应为 {:?}", expected),
+    }
+}
+
+fn parse_json_value(chars: &mut JsonChars) -> Result<JsonValue> {
+    skip_json_whitespace(chars);
+    match chars.peek().copied() {
+        Some('{') => {
+            chars.next();
+            let mut fields = Vec::new();
+            skip_json_whitespace(chars);
+            if chars.next_if_eq(&'}').is_some() {
+                return Ok(JsonValue::Object(fields));
+            }
+            loop {
+                expect_json_char(chars, '"')?;
+                let key = parse_json_string(chars)?;
+                expect_json_char(chars, ':')?;
+                fields.push((key, parse_json_value(chars)?));
+                skip_json_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some('}') => return Ok(JsonValue::Object(fields)),
+                    _ => bail!("JSON 对象中应为 ',' 或 '}}'"),
+                }
+            }
+        }
+        Some('[') => {
+            chars.next();
+            let mut items = Vec::new();
+            skip_json_whitespace(chars);
+            if chars.next_if_eq(&']').is_some() {
+                return Ok(JsonValue::Array(items));
+            }
+            loop {
+                items.push(parse_json_value(chars)?);
+                skip_json_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some(']') => return Ok(JsonValue::Array(items)),
+                    _ => bail!("JSON 数组中应为 ',' 或 ']'"),
+                }
+            }
+        }
+        Some('"') => {
+            chars.next();
+            Ok(JsonValue::String(parse_json_string(chars)?))
+        }
+        Some(c) if c == '-' || c.is_ascii_digit() => {
+            let mut number = String::new();
+            while let Some(c) =
+                chars.next_if(|c| matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+            {
+                number.push(c);
+            }
+            number
+                .parse()
+                .map(JsonValue::Number)
+                .map_err(|_| anyhow!("无效的 JSON 数字: {}", number))
+        }
+        Some(c) if c.is_ascii_alphabetic() => {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(char::is_ascii_alphabetic) {
+                word.push(c);
+            }
+            match word.as_str() {
+                "null" => Ok(JsonValue::Null),
+                "true" => Ok(JsonValue::Bool(true)),
+                "false" => Ok(JsonValue::Bool(false)),
+                _ => bail!("无效的 JSON 值: {}", word),
+            }
+        }
+        Some(c) => bail!("无效的 JSON 字符 {:?}", c),
+        None => bail!("JSON 意外结束"),
+    }
+}
+
+/// 解析开头引号之后的 JSON 字符串，包括 `\uXXXX` 与代理对
+fn parse_json_string(chars: &mut JsonChars) -> Result<String> {
+    fn hex4(chars: &mut JsonChars) -> Result<u32> {
+        let digits: String = chars.by_ref().take(4).collect();
+        u32::from_str_radix(&digits, 16).map_err(|_| anyhow!("无效的 JSON 转义 \\u{}", digits))
+    }
+
+    let mut out = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(out),
+            Some('\\') => match chars.next() {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                Some('/') => out.push('/'),
+                Some('b') => out.push('\u{8}'),
+                Some('f') => out.push('\u{c}'),
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some('t') => out.push('\t'),
+                Some('u') => {
+                    let mut code = hex4(chars)?;
+                    if (0xD800..0xDC00).contains(&code) {
+                        if chars.next() != Some('\\') || chars.next() != Some('u') {
+                            bail!("JSON 字符串中的代理对不完整");
+                        }
+                        let low = hex4(chars)?;
+                        if !(0xDC00..0xE000).contains(&low) {
+                            bail!("JSON 字符串中的代理对无效");
+                        }
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                    }
+                    out.push(char::from_u32(code).ok_or_else(|| anyhow!("无效的 JSON 字符"))?);
+                }
+                _ => bail!("无效的 JSON 转义"),
+            },
+            Some(c) => out.push(c),
+            None => bail!("JSON 字符串未结束"),
+        }
+    }
+}
+
+/// 读取此前导出的扫描结果中的项目路径，支持 json、json-lines 与 CSV 报告
+pub fn read_scan_paths(content: &str) -> Result<Vec<PathBuf>> {
+    let path_of = |project: &JsonValue| {
+        project
+            .get("path")
+            .and_then(JsonValue::as_str)
+            .map(PathBuf::from)
+            .ok_or_else(|| anyhow!("项目缺少 path 字段"))
+    };
+
+    if content.trim_start().starts_with('{') {
+        // 整个文件是一个含 projects 的对象时为 json 报告，否则按行解析 json-lines 报告
+        if let Ok(report) = parse_json(content) {
+            if let Some(JsonValue::Array(projects)) = report.get("projects") {
+                return projects.iter().map(path_of).collect();
+            }
+        }
+        let mut paths = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event =
+                parse_json(line).with_context(|| format!("第 {} 行不是有效的 JSON", i + 1))?;
+            if event.get("event").and_then(JsonValue::as_str) == Some("found") {
+                paths.push(path_of(&event).with_context(|| format!("第 {} 行", i + 1))?);
+            }
+        }
+        return Ok(paths);
+    }
+
+    let rows = parse_csv(content);
+    match rows.first() {
+        Some(header) if header.first().map(String::as_str) == Some("path") => {}
+        _ => bail!("不是 json、json-lines 或 CSV 报告"),
+    }
+    Ok(rows
+        .iter()
+        .skip(1)
+        .filter_map(|row| row.first().filter(|path| !path.is_empty()))
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// 按需为 CSV 字段加引号（含逗号、引号或换行时）
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// 解析 CSV 内容为各行字段，支持带引号（含逗号、引号或换行）的字段
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+/// CSV 报告，每个项目一行
+fn csv_report(records: &[ProjectRecord]) -> String {
+    let mut content = String::from(
+        "path,target_size,target_bytes,selected,cleaned,error,attempts,remaining_bytes,trashed,measure_ms,clean_ms\n",
+    );
+    for record in records {
+        let row = [
+            csv_field(&record.path.to_string_lossy()),
+            csv_field(&record.target_size),
+            record
+                .target_bytes
+                .map(|b| b.to_string())
+                .unwrap_or_default(),
+            record.selected.to_string(),
+            record.cleaned.to_string(),
+            csv_field(record.error.as_deref().unwrap_or("")),
+            record.attempts.to_string(),
+            record
+                .remaining_bytes
+                .map(|b| b.to_string())
+                .unwrap_or_default(),
+            record.trashed.to_string(),
+            record.measure_time.as_millis().to_string(),
+            record
+                .clean_time
+                .map(|t| t.as_millis().to_string())
+                .unwrap_or_default(),
+        ];
+        content.push_str(&row.join(","));
+        content.push('\n');
+    }
+    content
+}
+
+/// 转义 Markdown 表格单元格中的竖线与换行
+fn markdown_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// GitHub 风格的 Markdown 表格，附合计与生成时间
+fn markdown_report(records: &[ProjectRecord], units: SizeUnits) -> String {
+    let mut content = String::from("| # | 路径 | 大小 | 深度 |\n|---:|---|---:|---:|\n");
+    for (i, record) in records.iter().enumerate() {
+        content.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            i + 1,
+            markdown_cell(&record.path.to_string_lossy()),
+            markdown_cell(&record.target_size),
+            record.depth
+        ));
+    }
+    content.push_str(&format!(
+        "\n合计: {} 跨 {} 个项目\n\n生成时间: {}\n",
+        format_bytes(total_bytes(records), units),
+        records.len(),
+        format_utc_time(SystemTime::now())
+    ));
+    content
+}
+
+/// 将时间格式化为 UTC 的 `YYYY-MM-DD HH:MM:SS UTC`
+fn format_utc_time(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // 由 1970-01-01 起的天数换算公历日期（Howard Hinnant 的 civil_from_days 算法）
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 不访问磁盘、target 为给定字节数的处理结果
+    fn record(name: &str, bytes: u64) -> ProjectRecord {
+        ProjectRecord {
+            path: PathBuf::from(name),
+            target_size: format_bytes(bytes, SizeUnits::Binary),
+            target_bytes: Some(bytes),
+            depth: 1,
+            selected: false,
+            cleaned: false,
+            error: None,
+            attempts: 0,
+            remaining_bytes: None,
+            trashed: false,
+            measure_time: std::time::Duration::ZERO,
+            clean_time: None,
+        }
+    }
+
+    #[test]
+    fn report_totals_sum_exact_bytes() {
+        // 1075 字节显示为 "1.0KB"（向下取整），1100 字节显示为 "1.1KB"（向上取整），
+        // 按显示值相加为 21.0KB，精确总和为 21750 字节即 21.2KB
+        let mut stats = TraverseStats::default();
+        for i in 0..10 {
+            stats.records.push(record(&format!("down{}", i), 1075));
+            stats.records.push(record(&format!("up{}", i), 1100));
+        }
+        stats.found = stats.records.len();
+        assert_eq!(total_bytes(&stats.records), 21_750);
+        let units = SizeUnits::Binary;
+        assert!(json_summary_line(&stats).contains("\"total_bytes\":21750"));
+        assert!(json_report(&stats).contains("\"total_bytes\":21750"));
+        assert!(markdown_report(&stats.records, units).contains("合计: 21.2KB 跨 20 个项目"));
+        assert!(human_report(&stats.records, units).contains("21.2KB  合计 20 个项目"));
+    }
+
+    #[test]
+    fn json_strings_round_trip() {
+        for s in [
+            "plain",
+            "we\"ird\\path",
+            "tab\tand\nnewline",
+            "\u{1}控制",
+            "emoji 🦀",
+        ] {
+            let parsed = parse_json(&json_string(s)).unwrap();
+            assert_eq!(parsed, JsonValue::String(s.to_string()));
+        }
+        // 其他工具可能把非 ASCII 字符写成代理对
+        let parsed = parse_json(r#""\ud83e\udd80\u00e9""#).unwrap();
+        assert_eq!(parsed, JsonValue::String("🦀é".to_string()));
+    }
+
+    #[test]
+    fn parses_nested_json() {
+        let value = parse_json(r#" {"a": [1, -2.5e3, true, null], "b": {}} "#).unwrap();
+        assert_eq!(
+            value.get("a"),
+            Some(&JsonValue::Array(vec![
+                JsonValue::Number(1.0),
+                JsonValue::Number(-2500.0),
+                JsonValue::Bool(true),
+                JsonValue::Null,
+            ]))
+        );
+        assert_eq!(value.get("b"), Some(&JsonValue::Object(Vec::new())));
+        assert!(parse_json("{\"a\": 1} x").is_err());
+        assert!(parse_json("[1, 2").is_err());
+    }
+
+    #[test]
+    fn reads_paths_from_every_report_format() {
+        let json = "{\"projects\":[\n  {\"path\":\"/a\",\"depth\":1},\n  {\"path\":\"/b,c\"}\n],\"summary\":{}}\n";
+        let json_lines = "{\"event\":\"found\",\"path\":\"/a\"}\n{\"event\":\"found\",\"path\":\"/b,c\"}\n{\"event\":\"summary\",\"found\":2}\n";
+        let csv = "path,target_size\n/a,1B\n\"/b,c\",2B\n";
+        let expected = [PathBuf::from("/a"), PathBuf::from("/b,c")];
+        for content in [json, json_lines, csv] {
+            assert_eq!(read_scan_paths(content).unwrap(), expected);
+        }
+        assert!(read_scan_paths("not a report").is_err());
+    }
+}
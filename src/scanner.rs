@@ -0,0 +1,294 @@
+use crate::ignore_rules::{Excludes, IgnoreStack};
+use crate::size_cache::SizeCache;
+use crate::{project_layout, CargoProject, CleanProfile, ProjectKind, SizeUnits};
+use anyhow::Result;
+use std::collections::{HashSet, VecDeque};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+/// 影响遍历与项目识别的选项，各字段与同名命令行参数对应
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// 要识别的项目类型
+    pub detect: Vec<ProjectKind>,
+    /// 统计大小时只计 target 下该 profile 的子目录（仅 Rust 项目）
+    pub profile: CleanProfile,
+    /// 只报告深度不小于该值的项目（扫描起点为 0），更浅的目录仍会遍历
+    pub min_depth: Option<u32>,
+    /// 进入隐藏目录
+    pub scan_hidden: bool,
+    /// 跟随指向目录的符号链接
+    pub follow_symlinks: bool,
+    /// 进入已找到项目的构建目录
+    pub scan_inside_target: bool,
+    /// 读取每层目录的 `.gitignore` 与 `.ignore`，跳过其中忽略的子目录
+    pub respect_gitignore: bool,
+    /// 排除规则，语法与 `.gitignore` 相同，相对于各扫描起点
+    pub exclude: Vec<String>,
+    /// 包含规则，语法同上；不为空时只报告位于匹配目录中的项目
+    pub include: Vec<String>,
+    /// 格式化项目 `target_size` 时使用的单位制
+    pub units: SizeUnits,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            detect: vec![ProjectKind::Rust],
+            profile: CleanProfile::All,
+            min_depth: None,
+            scan_hidden: false,
+            follow_symlinks: false,
+            scan_inside_target: false,
+            respect_gitignore: false,
+            exclude: Vec::new(),
+            include: Vec::new(),
+            units: SizeUnits::Binary,
+        }
+    }
+}
+
+/// 遍历过程中依次产生的事件，`depth` 为所在目录相对扫描起点的深度
+#[derive(Debug)]
+pub enum ScanEvent<'a> {
+    /// 开始处理目录
+    Enter { dir: &'a Path, depth: usize },
+    /// 找到项目
+    Found { project: CargoProject, depth: usize },
+    /// 项目已处理过（经由符号链接或重叠的扫描起点再次访问），不再进入其子目录
+    Duplicate { project: CargoProject, depth: usize },
+    /// 无权限读取目录，跳过其子目录
+    PermissionDenied { dir: &'a Path, depth: usize },
+    /// 读取目录失败，跳过其子目录
+    ReadError {
+        dir: &'a Path,
+        depth: usize,
+        error: std::io::Error,
+    },
+    /// 不影响继续遍历的问题，如忽略文件解析失败
+    Warning { message: String, depth: usize },
+}
+
+/// 按广度优先依次遍历各扫描起点，每个事件调用一次 `on_event`，回调返回 `Break` 时停止遍历
+///
+/// 多个起点共享已处理项目等状态，起点重叠时同一项目只报告一次。
+/// 项目大小经由 `cache` 统计，调用方负责在结束后保存缓存
+pub fn walk(
+    roots: &[PathBuf],
+    options: &ScanOptions,
+    cache: &mut SizeCache,
+    mut on_event: impl FnMut(ScanEvent<'_>) -> ControlFlow<()>,
+) -> Result<()> {
+    // 排除与包含规则相对于各自的扫描起点
+    let root_excludes = roots
+        .iter()
+        .map(|root| Excludes::new(root, &options.exclude, &options.include))
+        .collect::<Result<Vec<_>>>()?;
+    // 已处理过的项目，避免经由符号链接重复处理同一项目
+    let mut seen_projects: HashSet<CargoProject> = HashSet::new();
+
+    // 已发现的工作区根目录及其 exclude 的目录
+    let mut workspaces: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+
+    // 跟随符号链接时记录已访问目录的规范化路径，防止链接成环导致无限遍历
+    let mut visited_dirs: HashSet<PathBuf> = HashSet::new();
+
+    // 使用 VecDeque 作为队列实现BFS遍历
+    // 每个目录附带从起点叠加下来的忽略规则（仅 respect_gitignore 时读取）
+    let mut dir_queue: VecDeque<(PathBuf, usize, IgnoreStack)> = VecDeque::new();
+
+    for (root, excludes) in roots.iter().zip(&root_excludes) {
+        dir_queue.push_back((root.clone(), 0, IgnoreStack::default()));
+        'traverse: while let Some((current_dir, depth, ignore_stack)) = dir_queue.pop_front() {
+            if options.follow_symlinks {
+                let canonical = current_dir
+                    .canonicalize()
+                    .unwrap_or_else(|_| current_dir.clone());
+                if !visited_dirs.insert(canonical) {
+                    continue;
+                }
+            }
+
+            if on_event(ScanEvent::Enter {
+                dir: &current_dir,
+                depth,
+            })
+            .is_break()
+            {
+                return Ok(());
+            }
+
+            // 检查是否有 Cargo.toml 且 target 目录存在（detect 指定其他类型时同理）
+            let cargo_toml = current_dir.join("Cargo.toml");
+            let build_dirs: Vec<(ProjectKind, PathBuf)> = options
+                .detect
+                .iter()
+                .filter_map(|&kind| {
+                    let (marker, build_dir) = project_layout(kind);
+                    let build_dir = current_dir.join(build_dir);
+                    (current_dir.join(marker).exists() && build_dir.exists())
+                        .then_some((kind, build_dir))
+                })
+                .collect();
+            // 浅于 min_depth 或不满足包含规则的项目不处理，但仍继续遍历其子目录
+            let wanted = options.min_depth.is_none_or(|min| depth >= min as usize)
+                && excludes.matches_filters(&current_dir);
+            // 工作区成员的产物默认在工作区根目录的 target 中，随工作区一并处理；
+            // 成员自带的 target（如曾单独构建留下的）仍作为项目报告，并记录所属工作区
+            let workspace = cargo_toml
+                .exists()
+                .then(|| read_workspace_excludes(&current_dir))
+                .flatten();
+            let member_of = match workspace {
+                Some(_) => None,
+                None => owning_workspace(&current_dir, &workspaces),
+            };
+            for (kind, _) in build_dirs.iter().filter(|_| wanted) {
+                let mut project = CargoProject::new(
+                    current_dir.clone(),
+                    depth,
+                    *kind,
+                    options.profile,
+                    options.units,
+                    cache,
+                );
+                if *kind == ProjectKind::Rust {
+                    project.workspace_root = member_of.clone();
+                }
+                if seen_projects.contains(&project) {
+                    // 忽略回调结果：无论如何都不再处理该目录
+                    let _ = on_event(ScanEvent::Duplicate { project, depth });
+                    continue 'traverse;
+                }
+
+                if on_event(ScanEvent::Found {
+                    project: project.clone(),
+                    depth,
+                })
+                .is_break()
+                {
+                    return Ok(());
+                }
+                seen_projects.insert(project);
+            }
+            if let Some(excludes) = workspace {
+                workspaces.push((current_dir.clone(), excludes));
+            }
+
+            let ignore_stack = if options.respect_gitignore {
+                let mut warnings = Vec::new();
+                let ignore_stack = ignore_stack.enter(&current_dir, &mut warnings);
+                for message in warnings {
+                    if on_event(ScanEvent::Warning { message, depth }).is_break() {
+                        return Ok(());
+                    }
+                }
+                ignore_stack
+            } else {
+                ignore_stack
+            };
+
+            // 收集子目录
+            let flow = match std::fs::read_dir(&current_dir) {
+                Ok(entries) => {
+                    let sub_dirs: Vec<(PathBuf, usize, IgnoreStack)> = entries
+                        .filter_map(|entry| entry.ok())
+                        // 默认不跟随指向目录的符号链接
+                        .filter(|e| match e.file_type() {
+                            Ok(file_type) if file_type.is_symlink() => {
+                                options.follow_symlinks && e.path().is_dir()
+                            }
+                            Ok(file_type) => file_type.is_dir(),
+                            Err(_) => false,
+                        })
+                        // 默认不进入隐藏目录（起点本身不受影响）
+                        .filter(|e| options.scan_hidden || !is_hidden_dir(&e.path()))
+                        // 默认不进入项目的 target 目录，其中嵌套项目的产物已计入本项目大小
+                        .filter(|e| {
+                            options.scan_inside_target
+                                || !build_dirs.iter().any(|(_, dir)| e.path() == *dir)
+                        })
+                        // 跳过排除规则匹配的子目录
+                        .filter(|e| !excludes.is_excluded(&e.path()))
+                        // 跳过被 .gitignore / .ignore 忽略的子目录
+                        .filter(|e| !ignore_stack.is_ignored(&e.path()))
+                        .map(|e| (e.path(), depth + 1, ignore_stack.clone()))
+                        .collect();
+
+                    // BFS：直接将子目录添加到队列末尾
+                    dir_queue.extend(sub_dirs);
+                    ControlFlow::Continue(())
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                    on_event(ScanEvent::PermissionDenied {
+                        dir: &current_dir,
+                        depth,
+                    })
+                }
+                Err(error) => on_event(ScanEvent::ReadError {
+                    dir: &current_dir,
+                    depth,
+                    error,
+                }),
+            };
+            if flow.is_break() {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 扫描单个起点，返回找到的项目，不读写大小缓存，也不输出任何内容
+pub fn scan(root: &Path, options: &ScanOptions) -> Result<Vec<CargoProject>> {
+    let mut projects = Vec::new();
+    walk(
+        &[root.to_path_buf()],
+        options,
+        &mut SizeCache::disabled(),
+        |event| {
+            if let ScanEvent::Found { project, .. } = event {
+                projects.push(project);
+            }
+            ControlFlow::Continue(())
+        },
+    )?;
+    Ok(projects)
+}
+
+/// 读取项目的 `[workspace]` 配置，返回其 `exclude` 的目录；不是工作区根目录时返回 None
+fn read_workspace_excludes(project_dir: &Path) -> Option<Vec<PathBuf>> {
+    let content = std::fs::read_to_string(project_dir.join("Cargo.toml")).ok()?;
+    let manifest: toml::Table = content.parse().ok()?;
+    let workspace = manifest.get("workspace")?;
+    let excludes = workspace
+        .get("exclude")
+        .and_then(|exclude| exclude.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|item| project_dir.join(item))
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(excludes)
+}
+
+/// 查找包含该目录的工作区根目录（位于 exclude 中的目录不属于工作区）
+fn owning_workspace(dir: &Path, workspaces: &[(PathBuf, Vec<PathBuf>)]) -> Option<PathBuf> {
+    workspaces
+        .iter()
+        .filter(|(root, excludes)| {
+            dir.starts_with(root) && !excludes.iter().any(|exclude| dir.starts_with(exclude))
+        })
+        .map(|(root, _)| root.clone())
+        .next_back()
+}
+
+/// 判断目录是否为隐藏目录（名称以 `.` 开头）
+fn is_hidden_dir(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().starts_with('.'))
+        .unwrap_or(false)
+}
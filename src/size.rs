@@ -0,0 +1,131 @@
+use rayon::iter::{ParallelBridge, ParallelIterator};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// 输出大小时使用的单位制
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SizeUnits {
+    /// 1024 进制，标为 KB/MB/GB
+    #[default]
+    Binary,
+    /// 1024 进制，标为 KiB/MiB/GiB
+    Iec,
+    /// 1000 进制，标为 KB/MB/GB
+    Si,
+}
+
+/// 目录的统计结果，大小、文件数与最新修改时间在同一次遍历中得到
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirSize {
+    /// 能读取到的文件的总大小（字节）
+    pub bytes: u64,
+    /// 无法读取而未计入的子目录与文件数，不为 0 时实际大小可能更大
+    pub unreadable: u64,
+    /// 文件数
+    pub files: u64,
+    /// 最新文件的修改时间，没有文件时为 None
+    pub newest_mtime: Option<SystemTime>,
+    /// 大小取自缓存、没有遍历目录，此时只有 `bytes` 有效
+    pub from_cache: bool,
+}
+
+impl DirSize {
+    /// 一个无法读取的子项
+    const UNREADABLE: Self = Self {
+        bytes: 0,
+        unreadable: 1,
+        files: 0,
+        newest_mtime: None,
+        from_cache: false,
+    };
+}
+
+impl std::ops::Add for DirSize {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            bytes: self.bytes + other.bytes,
+            unreadable: self.unreadable + other.unreadable,
+            files: self.files + other.files,
+            newest_mtime: self.newest_mtime.max(other.newest_mtime),
+            from_cache: self.from_cache && other.from_cache,
+        }
+    }
+}
+
+/// 计算目录的磁盘占用大小（字节），`skip` 中的直接子项不计入
+///
+/// 无法读取的子项不计入；需要知道统计是否完整时使用 [`measure_dir_size`]
+pub fn get_dir_size(path: &Path, skip: &[&str]) -> std::io::Result<u64> {
+    Ok(measure_dir_size(path, skip)?.bytes)
+}
+
+/// 计算目录的磁盘占用大小，同时统计文件数、最新文件的修改时间与无法读取而未计入的子项数
+///
+/// 只有 `path` 本身无法读取时返回错误
+pub fn measure_dir_size(path: &Path, skip: &[&str]) -> std::io::Result<DirSize> {
+    // 子目录并行递归统计，大型 target 的文件数可达数十万
+    fn entry_size(entry: std::io::Result<std::fs::DirEntry>) -> DirSize {
+        let Ok(entry) = entry else {
+            return DirSize::UNREADABLE;
+        };
+        // file_type 与 DirEntry::metadata 都不跟随符号链接：指向目录的链接按链接本身计入，
+        // 不会重复统计链接目标，也不会因链接成环而无限递归
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            dir_size_iter(&entry.path())
+        } else {
+            match entry.metadata() {
+                Ok(metadata) => DirSize {
+                    bytes: metadata.len(),
+                    files: 1,
+                    newest_mtime: metadata.modified().ok(),
+                    ..DirSize::default()
+                },
+                Err(_) => DirSize::UNREADABLE,
+            }
+        }
+    }
+
+    fn dir_size_iter(path: &Path) -> DirSize {
+        match std::fs::read_dir(path) {
+            Ok(entries) => entries
+                .par_bridge()
+                .map(entry_size)
+                .reduce(DirSize::default, |a, b| a + b),
+            Err(_) => DirSize::UNREADABLE,
+        }
+    }
+
+    Ok(std::fs::read_dir(path)?
+        .par_bridge()
+        .filter(|entry| match entry {
+            Ok(entry) => !skip.iter().any(|name| entry.file_name() == *name),
+            Err(_) => true,
+        })
+        .map(entry_size)
+        .reduce(DirSize::default, |a, b| a + b))
+}
+
+/// 将字节数按给定单位制格式化为可读字符串（如 "20.0MB", "1.2GB"）
+pub fn format_bytes(bytes: u64, units: SizeUnits) -> String {
+    let (base, [kb, mb, gb]) = match units {
+        SizeUnits::Binary => (1024, ["KB", "MB", "GB"]),
+        SizeUnits::Iec => (1024, ["KiB", "MiB", "GiB"]),
+        SizeUnits::Si => (1000, ["KB", "MB", "GB"]),
+    };
+    let kb_bytes: u64 = base;
+    let mb_bytes = kb_bytes * base;
+    let gb_bytes = mb_bytes * base;
+
+    if bytes >= gb_bytes {
+        format!("{:.1}{}", bytes as f64 / gb_bytes as f64, gb)
+    } else if bytes >= mb_bytes {
+        format!("{:.1}{}", bytes as f64 / mb_bytes as f64, mb)
+    } else if bytes >= kb_bytes {
+        format!("{:.1}{}", bytes as f64 / kb_bytes as f64, kb)
+    } else {
+        format!("{}B", bytes)
+    }
+}
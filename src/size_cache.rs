@@ -0,0 +1,179 @@
+use crate::{measure_dir_size, DirSize};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use toml::{Table, Value};
+
+/// 目录大小缓存，保存在 `<缓存目录>/clean_cargo_projects/sizes.toml`
+///
+/// 以目录的规范化路径为键，记录统计时的指纹、统计时间与大小。
+/// 指纹是目录本身及 [`FINGERPRINT_DEPTH`] 层以内子目录的最新修改时间：
+/// cargo 构建时会在 `target/debug/deps`、`target/debug/build` 等目录中新增或替换文件，
+/// 从而更新这些目录的修改时间；只读取这几层目录，不必遍历整个 target。
+/// 更深处的改动与原地改写文件不会改变指纹，因此缓存只在 [`MAX_AGE`] 内有效
+pub struct SizeCache {
+    /// 为 None 时不读写缓存（`--no-cache`）
+    file: Option<PathBuf>,
+    entries: HashMap<PathBuf, Entry>,
+    dirty: bool,
+}
+
+/// 指纹只包含这几层以内的子目录（`target/<triple>/debug/deps` 为第 3 层）
+const FINGERPRINT_DEPTH: usize = 3;
+
+/// 缓存的有效期，超过后即使指纹未变也重新统计
+const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// 一个目录的缓存
+#[derive(Clone, Copy)]
+struct Entry {
+    /// 统计时的指纹（纳秒时间戳）
+    fingerprint: i64,
+    /// 统计时间（秒级时间戳）
+    measured: i64,
+    /// 大小（字节）
+    size: u64,
+}
+
+impl SizeCache {
+    /// 不读写磁盘的空缓存
+    pub fn disabled() -> SizeCache {
+        SizeCache {
+            file: None,
+            entries: HashMap::new(),
+            dirty: false,
+        }
+    }
+
+    /// 读取缓存文件；文件不存在或无法解析时从空缓存开始
+    pub fn load() -> SizeCache {
+        let Some(file) = cache_file() else {
+            return SizeCache::disabled();
+        };
+        let entries = std::fs::read_to_string(&file)
+            .ok()
+            .and_then(|content| content.parse::<Table>().ok())
+            .map(|table| {
+                table
+                    .iter()
+                    .filter_map(|(path, entry)| {
+                        let entry = Entry {
+                            fingerprint: entry.get("mtime")?.as_integer()?,
+                            measured: entry.get("measured")?.as_integer()?,
+                            size: entry.get("size")?.as_integer()? as u64,
+                        };
+                        Some((PathBuf::from(path), entry))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        SizeCache {
+            file: Some(file),
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// 删除缓存文件（`--clear-cache`）
+    pub fn clear() -> std::io::Result<()> {
+        match cache_file() {
+            Some(file) if file.exists() => std::fs::remove_file(file),
+            _ => Ok(()),
+        }
+    }
+
+    /// 统计目录大小，指纹未变且未超过有效期时直接使用缓存
+    ///
+    /// 只缓存大小：指纹不反映所有文件的改动，文件的修改时间因此不可靠，命中缓存时不提供。
+    /// 有子项无法读取时结果不完整，不写入缓存
+    pub fn dir_size(&mut self, dir: &Path) -> std::io::Result<DirSize> {
+        if self.file.is_none() {
+            return measure_dir_size(dir, &[]);
+        }
+        let key = dir.canonicalize()?;
+        let fingerprint = newest_dir_mtime(dir);
+        let now = unix_secs(SystemTime::now());
+        if let (Some(fingerprint), Some(entry)) = (fingerprint, self.entries.get(&key)) {
+            let fresh = now.saturating_sub(entry.measured) < MAX_AGE.as_secs() as i64;
+            if fingerprint == entry.fingerprint && fresh {
+                return Ok(DirSize {
+                    bytes: entry.size,
+                    from_cache: true,
+                    ..DirSize::default()
+                });
+            }
+        }
+
+        let size = measure_dir_size(dir, &[])?;
+        if let (Some(fingerprint), 0) = (fingerprint, size.unreadable) {
+            let entry = Entry {
+                fingerprint,
+                measured: now,
+                size: size.bytes,
+            };
+            self.entries.insert(key, entry);
+            self.dirty = true;
+        }
+        Ok(size)
+    }
+
+    /// 写回缓存文件，同时丢弃已不存在的目录
+    pub fn save(&self) -> Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+        let mut table = Table::new();
+        for (path, entry) in &self.entries {
+            if !path.exists() {
+                continue;
+            }
+            let mut item = Table::new();
+            item.insert("mtime".into(), Value::Integer(entry.fingerprint));
+            item.insert("measured".into(), Value::Integer(entry.measured));
+            item.insert("size".into(), Value::Integer(entry.size as i64));
+            table.insert(path.to_string_lossy().into_owned(), Value::Table(item));
+        }
+        file.parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(file, table.to_string()))
+            .with_context(|| format!("写入大小缓存失败: {}", file.display()))
+    }
+}
+
+fn cache_file() -> Option<PathBuf> {
+    Some(crate::cache_dir()?.join("sizes.toml"))
+}
+
+fn unix_secs(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// 目录及其 [`FINGERPRINT_DEPTH`] 层以内子目录中最新的修改时间（纳秒时间戳），不跟随符号链接
+fn newest_dir_mtime(dir: &Path) -> Option<i64> {
+    fn visit(dir: &Path, depth: usize, newest: &mut SystemTime) {
+        if let Ok(modified) = std::fs::metadata(dir).and_then(|m| m.modified()) {
+            *newest = (*newest).max(modified);
+        }
+        if depth == FINGERPRINT_DEPTH {
+            return;
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                visit(&entry.path(), depth + 1, newest);
+            }
+        }
+    }
+
+    let mut newest = UNIX_EPOCH;
+    visit(dir, 0, &mut newest);
+    let nanos = newest.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    i64::try_from(nanos).ok().filter(|&nanos| nanos > 0)
+}
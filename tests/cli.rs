@@ -0,0 +1,218 @@
+//! 运行编译好的程序，检查命令行参数组合的端到端行为
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn make_cargo_project(dir: &Path, target_bytes: usize) {
+    fs::create_dir_all(dir.join("target")).unwrap();
+    fs::write(dir.join("Cargo.toml"), "").unwrap();
+    fs::write(dir.join("target/f"), vec![0u8; target_bytes]).unwrap();
+}
+
+/// 运行程序；家目录与缓存目录指向空的临时目录，不读取用户配置
+fn run(args: &[&str]) -> Output {
+    let home = TempDir::new().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_clean_cargo_projects"))
+        .args(["--no-cache", "--color", "never"])
+        .args(args)
+        .env("HOME", home.path())
+        .env("XDG_CACHE_HOME", home.path().join(".cache"))
+        .env_remove("CLEAN_CARGO_ASSUME_YES")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+/// CSV 报告中各行的项目路径（第一列），不含表头
+fn csv_paths(file: &Path) -> Vec<String> {
+    fs::read_to_string(file)
+        .unwrap()
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').next().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn stats_only_export_omits_projects_below_min_size() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("big"), 64 * 1024);
+    make_cargo_project(&root.path().join("small"), 1024);
+    let report = root.path().join("report.csv");
+
+    run(&[
+        "--path",
+        root.path().to_str().unwrap(),
+        "--stats-only",
+        "--min-size",
+        "10KB",
+        "--csv",
+        report.to_str().unwrap(),
+    ]);
+
+    let paths = csv_paths(&report);
+    assert_eq!(paths.len(), 1, "{:?}", paths);
+    assert!(paths[0].ends_with("big"));
+}
+
+#[test]
+fn markdown_report_counts_only_listed_projects() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("big"), 64 * 1024);
+    make_cargo_project(&root.path().join("a|b"), 32 * 1024);
+    make_cargo_project(&root.path().join("small"), 1024);
+
+    let output = run(&[
+        "--path",
+        root.path().to_str().unwrap(),
+        "--output-format",
+        "markdown",
+        "--min-size",
+        "10KB",
+    ]);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("| ") && !line.starts_with("| #"))
+        .collect();
+    assert_eq!(rows.len(), 2, "{}", stdout);
+    assert!(rows.iter().any(|row| row.contains("a\\|b")));
+    assert!(!stdout.contains("small"));
+    assert!(stdout.contains("合计: 96.0KB 跨 2 个项目"), "{}", stdout);
+}
+
+/// json-lines 报告中各项目的路径
+fn json_lines_paths(stdout: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .filter(|line| line.contains("\"event\":\"found\""))
+        .map(|line| {
+            let start = line.find("\"path\":\"").unwrap() + "\"path\":\"".len();
+            let end = start + line[start..].find("\",").unwrap();
+            line[start..end].to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn from_scan_reads_back_every_report_format() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("a,b"), 2048);
+    make_cargo_project(&root.path().join("c"), 4096);
+    let root_arg = root.path().to_str().unwrap();
+    let scanned = run(&["--path", root_arg, "--json-lines"]);
+    let mut expected = json_lines_paths(&scanned.stdout);
+    expected.sort();
+    assert_eq!(expected.len(), 2);
+
+    for format in ["json", "json-lines", "csv"] {
+        let report = root.path().join(format!("scan.{}", format));
+        let report_arg = report.to_str().unwrap();
+        run(&[
+            "--path",
+            root_arg,
+            "--output-format",
+            format,
+            "--output",
+            report_arg,
+            "--stats-only",
+        ]);
+
+        let reloaded = run(&["--from-scan", report_arg, "--json-lines"]);
+        let mut paths = json_lines_paths(&reloaded.stdout);
+        paths.sort();
+        assert_eq!(paths, expected, "{}", format);
+    }
+}
+
+#[test]
+fn json_lines_lists_projects_in_fixture_tree() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("a"), 1000);
+    make_cargo_project(&root.path().join("nested/b"), 2000);
+    make_cargo_project(&root.path().join("vendor/c"), 3000);
+    // 没有 target 的不是候选项目
+    fs::create_dir_all(root.path().join("no_target")).unwrap();
+    fs::write(root.path().join("no_target/Cargo.toml"), "").unwrap();
+    let root_arg = root.path().to_str().unwrap();
+
+    let output = run(&["--path", root_arg, "--json-lines", "--exclude", "vendor"]);
+
+    let mut paths = json_lines_paths(&output.stdout);
+    paths.sort();
+    let expected: Vec<String> = ["a", "nested/b"]
+        .iter()
+        .map(|name| root.path().join(name).to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(paths, expected);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let summary = stdout.lines().last().unwrap();
+    assert_eq!(
+        summary,
+        "{\"event\":\"summary\",\"found\":2,\"listed\":2,\"total_bytes\":3000,\"scan_errors\":0}"
+    );
+    // 报告之外不向标准输出打印任何内容
+    assert_eq!(stdout.lines().count(), 3);
+}
+
+#[test]
+fn min_size_applies_to_empty_targets_listed_by_include_empty() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("big"), 64 * 1024);
+    make_cargo_project(&root.path().join("empty"), 0);
+    let root_arg = root.path().to_str().unwrap();
+
+    let output = run(&["--path", root_arg, "--json-lines", "--include-empty"]);
+    assert_eq!(json_lines_paths(&output.stdout).len(), 2);
+
+    let output = run(&[
+        "--path",
+        root_arg,
+        "--json-lines",
+        "--include-empty",
+        "--min-size",
+        "10KB",
+    ]);
+    let paths = json_lines_paths(&output.stdout);
+    assert_eq!(paths.len(), 1, "{:?}", paths);
+    assert!(paths[0].ends_with("big"));
+}
+
+#[test]
+fn no_lock_runs_while_another_instance_holds_the_lock() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("a"), 1000);
+    let cache = TempDir::new().unwrap();
+    let lock_dir = cache.path().join("clean_cargo_projects");
+    fs::create_dir_all(&lock_dir).unwrap();
+    let held = fs::File::create(lock_dir.join("lock")).unwrap();
+    held.try_lock().unwrap();
+
+    // 会清理项目的运行（此处不匹配任何项目），锁被占用时默认以退出码 3 退出
+    let clean = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_clean_cargo_projects"))
+            .arg("--path")
+            .arg(root.path())
+            .args(["--no-cache", "--ask-mode", "glob", "--select", "/nothing"])
+            .args(extra)
+            .env("HOME", cache.path())
+            .env("XDG_CACHE_HOME", cache.path())
+            .output()
+            .unwrap()
+    };
+    assert_eq!(clean(&[]).status.code(), Some(3));
+    let output = clean(&["--no-lock"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,438 @@
+use clean_cargo_projects::size_cache::SizeCache;
+use clean_cargo_projects::{
+    measure_dir_size, scan, walk, CargoProject, CleanProfile, ProjectKind, ScanEvent, ScanOptions,
+    SizeUnits,
+};
+use std::collections::HashSet;
+use std::fs;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// 在 `dir` 下创建项目：写入标志文件，并在构建目录中放入给定大小的文件
+fn make_project(dir: &Path, manifest: &str, build_dir: &str, sizes: &[usize]) {
+    fs::create_dir_all(dir.join(build_dir)).unwrap();
+    fs::write(dir.join(manifest), "").unwrap();
+    for (i, size) in sizes.iter().enumerate() {
+        fs::write(
+            dir.join(build_dir).join(format!("f{}", i)),
+            vec![0u8; *size],
+        )
+        .unwrap();
+    }
+}
+
+fn make_cargo_project(dir: &Path, sizes: &[usize]) {
+    make_project(dir, "Cargo.toml", "target", sizes);
+}
+
+/// 找到的项目相对于扫描起点的路径，按路径排序
+fn relative_paths(root: &Path, projects: &[CargoProject]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = projects
+        .iter()
+        .map(|p| p.path.strip_prefix(root).unwrap().to_path_buf())
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn finds_projects_with_target_sizes() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("a"), &[1000, 24]);
+    make_cargo_project(&root.path().join("b/c"), &[4096]);
+    // 没有 target 的不是候选项目
+    fs::create_dir_all(root.path().join("no_target")).unwrap();
+    fs::write(root.path().join("no_target/Cargo.toml"), "").unwrap();
+
+    let projects = scan(root.path(), &ScanOptions::default()).unwrap();
+
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [PathBuf::from("a"), PathBuf::from("b/c")]
+    );
+    let size_of = |name: &str| {
+        projects
+            .iter()
+            .find(|p| p.path.ends_with(name))
+            .and_then(|p| p.target_bytes)
+    };
+    assert_eq!(size_of("a"), Some(1024));
+    assert_eq!(size_of("c"), Some(4096));
+}
+
+#[test]
+fn does_not_descend_into_target() {
+    let root = TempDir::new().unwrap();
+    let outer = root.path().join("outer");
+    make_cargo_project(&outer, &[10]);
+    make_cargo_project(&outer.join("target/fixture"), &[10]);
+    make_cargo_project(&outer.join("crates/inner"), &[10]);
+
+    let projects = scan(root.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [PathBuf::from("outer"), PathBuf::from("outer/crates/inner")]
+    );
+
+    let options = ScanOptions {
+        scan_inside_target: true,
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [
+            PathBuf::from("outer"),
+            PathBuf::from("outer/crates/inner"),
+            PathBuf::from("outer/target/fixture"),
+        ]
+    );
+}
+
+#[test]
+fn reports_workspace_members_with_their_own_target() {
+    let root = TempDir::new().unwrap();
+    let ws = root.path().join("ws");
+    make_cargo_project(&ws, &[10]);
+    fs::write(
+        ws.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"member\", \"shared\"]\nexclude = [\"standalone\"]\n",
+    )
+    .unwrap();
+    // 成员自带的 target 是 cargo clean 不会删除的残留产物，仍需报告
+    make_cargo_project(&ws.join("member"), &[3000]);
+    // 没有 target 的成员产物在工作区的 target 中，不单独报告
+    fs::create_dir_all(ws.join("shared")).unwrap();
+    fs::write(ws.join("shared/Cargo.toml"), "").unwrap();
+    make_cargo_project(&ws.join("standalone"), &[10]);
+
+    let projects = scan(root.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [
+            PathBuf::from("ws"),
+            PathBuf::from("ws/member"),
+            PathBuf::from("ws/standalone")
+        ]
+    );
+    let workspace_of = |name: &str| {
+        projects
+            .iter()
+            .find(|p| p.path.ends_with(name))
+            .unwrap()
+            .workspace_root
+            .clone()
+    };
+    assert_eq!(workspace_of("member"), Some(ws.clone()));
+    assert_eq!(workspace_of("ws"), None);
+    assert_eq!(workspace_of("standalone"), None);
+}
+
+#[test]
+fn applies_hidden_depth_and_exclude_filters() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(root.path(), &[10]);
+    make_cargo_project(&root.path().join(".hidden/p"), &[10]);
+    make_cargo_project(&root.path().join("vendor/p"), &[10]);
+    make_cargo_project(&root.path().join("src/p"), &[10]);
+
+    let options = ScanOptions {
+        min_depth: Some(1),
+        exclude: vec![String::from("vendor")],
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [PathBuf::from("src/p")]
+    );
+
+    let options = ScanOptions {
+        scan_hidden: true,
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [
+            PathBuf::from(""),
+            PathBuf::from(".hidden/p"),
+            PathBuf::from("src/p"),
+            PathBuf::from("vendor/p"),
+        ]
+    );
+}
+
+#[test]
+fn detects_other_project_kinds() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("rust"), &[10]);
+    make_project(
+        &root.path().join("web"),
+        "package.json",
+        "node_modules",
+        &[300],
+    );
+
+    let projects = scan(root.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [PathBuf::from("rust")]
+    );
+
+    let options = ScanOptions {
+        detect: vec![ProjectKind::Rust, ProjectKind::Node],
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    let web = projects
+        .iter()
+        .find(|p| p.kind == ProjectKind::Node)
+        .unwrap();
+    assert!(web.path.ends_with("web"));
+    assert_eq!(web.target_bytes, Some(300));
+    assert_eq!(projects.len(), 2);
+}
+
+#[test]
+fn include_patterns_limit_reported_projects() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("work/a"), &[10]);
+    make_cargo_project(&root.path().join("work/b/nested"), &[10]);
+    make_cargo_project(&root.path().join("personal/c"), &[10]);
+
+    let options = ScanOptions {
+        include: vec![String::from("/work/*")],
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [PathBuf::from("work/a"), PathBuf::from("work/b/nested")]
+    );
+}
+
+#[test]
+fn exclude_takes_precedence_over_include() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("work/a"), &[10]);
+    make_cargo_project(&root.path().join("work/vendor/v"), &[10]);
+    make_cargo_project(&root.path().join("personal/c"), &[10]);
+
+    // 只有排除规则时不限制其他位置的项目
+    let options = ScanOptions {
+        exclude: vec![String::from("vendor")],
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [PathBuf::from("personal/c"), PathBuf::from("work/a")]
+    );
+
+    let options = ScanOptions {
+        include: vec![String::from("work")],
+        exclude: vec![String::from("vendor")],
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [PathBuf::from("work/a")]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn projects_reached_through_symlink_are_equal() {
+    let root = TempDir::new().unwrap();
+    let real = root.path().join("real");
+    make_cargo_project(&real, &[10]);
+    let alias = root.path().join("alias");
+    std::os::unix::fs::symlink(&real, &alias).unwrap();
+
+    let mut cache = SizeCache::disabled();
+    let mut project = |path: &Path| {
+        CargoProject::new(
+            path.to_path_buf(),
+            1,
+            ProjectKind::Rust,
+            CleanProfile::All,
+            SizeUnits::Binary,
+            &mut cache,
+        )
+    };
+    let via_real = project(&real);
+    let via_alias = project(&alias);
+    assert_ne!(via_real.path, via_alias.path);
+    assert_eq!(via_real, via_alias);
+
+    let set: HashSet<CargoProject> = [via_real, via_alias].into_iter().collect();
+    assert_eq!(set.len(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn follows_symlinks_without_reporting_aliases_twice() {
+    let root = TempDir::new().unwrap();
+    let real = root.path().join("real");
+    make_cargo_project(&real, &[10]);
+    std::os::unix::fs::symlink(&real, root.path().join("alias")).unwrap();
+    // 指向祖先目录的链接不会导致无限遍历
+    std::os::unix::fs::symlink(root.path(), real.join("loop")).unwrap();
+
+    let projects = scan(root.path(), &ScanOptions::default()).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [PathBuf::from("real")]
+    );
+
+    let options = ScanOptions {
+        follow_symlinks: true,
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].canonical_path, real.canonicalize().unwrap());
+}
+
+#[cfg(unix)]
+#[test]
+fn target_size_does_not_follow_symlinks() {
+    let root = TempDir::new().unwrap();
+    let project = root.path().join("p");
+    make_cargo_project(&project, &[1000]);
+    let target = project.join("target");
+    // target 中指回项目的链接构成环，指向外部目录的链接也不计入其内容
+    std::os::unix::fs::symlink(&project, target.join("loop")).unwrap();
+    let outside = root.path().join("outside");
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(outside.join("big"), vec![0u8; 1 << 20]).unwrap();
+    std::os::unix::fs::symlink(&outside, target.join("external")).unwrap();
+
+    let link_bytes: u64 = ["loop", "external"]
+        .iter()
+        .map(|name| fs::symlink_metadata(target.join(name)).unwrap().len())
+        .sum();
+    let size = measure_dir_size(&target, &[]).unwrap();
+    assert_eq!(size.bytes, 1000 + link_bytes);
+    assert_eq!(size.files, 3);
+    assert_eq!(size.unreadable, 0);
+}
+
+#[test]
+fn overlapping_roots_report_duplicates() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("a"), &[10]);
+
+    let roots = [root.path().to_path_buf(), root.path().join("a")];
+    let mut found = Vec::new();
+    let mut duplicates = Vec::new();
+    walk(
+        &roots,
+        &ScanOptions::default(),
+        &mut SizeCache::disabled(),
+        |event| {
+            match event {
+                ScanEvent::Found { project, .. } => found.push(project.path),
+                ScanEvent::Duplicate { project, .. } => duplicates.push(project.path),
+                _ => {}
+            }
+            ControlFlow::Continue(())
+        },
+    )
+    .unwrap();
+    assert_eq!(found, [root.path().join("a")]);
+    assert_eq!(duplicates, [root.path().join("a")]);
+}
+
+#[test]
+fn bare_exclude_name_matches_at_any_depth() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("vendor/a"), &[10]);
+    make_cargo_project(&root.path().join("x/vendor/b"), &[10]);
+    make_cargo_project(&root.path().join("c"), &[10]);
+
+    let options = ScanOptions {
+        exclude: vec![String::from("vendor")],
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    assert_eq!(relative_paths(root.path(), &projects), [PathBuf::from("c")]);
+}
+
+#[test]
+fn exclude_pattern_with_slash_is_anchored_to_root() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("a/x/p"), &[10]);
+    make_cargo_project(&root.path().join("x/q"), &[10]);
+    make_cargo_project(&root.path().join("a/b/x/r"), &[10]);
+
+    let options = ScanOptions {
+        exclude: vec![String::from("*/x/*")],
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [PathBuf::from("a/b/x/r"), PathBuf::from("x/q")]
+    );
+}
+
+#[test]
+fn absolute_exclude_pattern_is_relative_to_root() {
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("skip"), &[10]);
+    make_cargo_project(&root.path().join("keep/skip"), &[10]);
+
+    let options = ScanOptions {
+        exclude: vec![root.path().join("skip").to_string_lossy().into_owned()],
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    assert_eq!(
+        relative_paths(root.path(), &projects),
+        [PathBuf::from("keep/skip")]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn absolute_exclude_pattern_through_symlinked_root() {
+    let dir = TempDir::new().unwrap();
+    let real = dir.path().join("real");
+    make_cargo_project(&real.join("skip"), &[10]);
+    make_cargo_project(&real.join("keep"), &[10]);
+    let link = dir.path().join("link");
+    std::os::unix::fs::symlink(&real, &link).unwrap();
+
+    // 规则经由链接路径或规范化路径写出时都应生效
+    for prefix in [&link, &real.canonicalize().unwrap()] {
+        let options = ScanOptions {
+            exclude: vec![prefix.join("skip").to_string_lossy().into_owned()],
+            ..ScanOptions::default()
+        };
+        let projects = scan(&link, &options).unwrap();
+        assert_eq!(relative_paths(&link, &projects), [PathBuf::from("keep")]);
+    }
+}
+
+#[test]
+fn format_bytes_uses_the_given_units() {
+    use clean_cargo_projects::format_bytes;
+    assert_eq!(format_bytes(1536, SizeUnits::Binary), "1.5KB");
+    assert_eq!(format_bytes(1536, SizeUnits::Iec), "1.5KiB");
+    assert_eq!(format_bytes(1536, SizeUnits::Si), "1.5KB");
+    assert_eq!(format_bytes(1_500_000, SizeUnits::Si), "1.5MB");
+    assert_eq!(format_bytes(999, SizeUnits::Si), "999B");
+
+    let root = TempDir::new().unwrap();
+    make_cargo_project(&root.path().join("a"), &[2000]);
+    let options = ScanOptions {
+        units: SizeUnits::Si,
+        ..ScanOptions::default()
+    };
+    let projects = scan(root.path(), &options).unwrap();
+    assert_eq!(projects[0].target_size, "2.0KB");
+}